@@ -20,6 +20,7 @@ import {
   saveFeatureRestrictions,
   type FeatureRestrictions,
 } from "@/lib/settings/feature-restrictions";
+import { recordAuditEvent } from "@/lib/security/audit-log";
 import { PasswordGate } from "@/components/admin/password-gate";
 import { PageLoading } from "@/components/ui/page-loading";
 import "./admin.css";
@@ -150,12 +151,14 @@ export default function AdminPage() {
         newNotes.trim() || undefined,
       );
       setEntries((prev) => [...prev, entry]);
+      void recordAuditEvent("whitelist_add", "success", { orgId: entry.orgId });
       setNewOrgId("");
       setNewOrgName("");
       setNewNotes("");
       setSuccess("Organization added successfully");
       setTimeout(() => setSuccess(null), 3000);
     } catch (error) {
+      void recordAuditEvent("whitelist_add", "failure", { orgId: newOrgId.trim() });
       setError(error instanceof Error ? error.message : "Failed to add organization");
     }
   }, [newOrgId, newOrgName, newNotes, whitelistManagedExternally]);
@@ -170,16 +173,19 @@ export default function AdminPage() {
       return;
     }
 
+    const orgId = entries.find((entry) => entry.id === id)?.orgId;
     try {
       setError(null);
       await removeOrgFromWhitelist(id);
       setEntries((prev) => prev.filter((entry) => entry.id !== id));
+      void recordAuditEvent("whitelist_remove", "success", { orgId });
       setSuccess("Organization removed successfully");
       setTimeout(() => setSuccess(null), 3000);
     } catch (error) {
+      void recordAuditEvent("whitelist_remove", "failure", { orgId });
       setError(error instanceof Error ? error.message : "Failed to remove organization");
     }
-  }, [whitelistManagedExternally]);
+  }, [entries, whitelistManagedExternally]);
 
   const handleStartEdit = useCallback((entry: OrgWhitelistEntry) => {
     if (whitelistManagedExternally) {
@@ -218,15 +224,19 @@ export default function AdminPage() {
       setEntries((prev) =>
         prev.map((entry) => (entry.id === updated.id ? updated : entry)),
       );
+      void recordAuditEvent("whitelist_update", "success", { orgId: updated.orgId });
       setEditingId(null);
       setEditOrgName("");
       setEditNotes("");
       setSuccess("Organization updated successfully");
       setTimeout(() => setSuccess(null), 3000);
     } catch (error) {
+      void recordAuditEvent("whitelist_update", "failure", {
+        orgId: entries.find((entry) => entry.id === editingId)?.orgId,
+      });
       setError(error instanceof Error ? error.message : "Failed to update organization");
     }
-  }, [editingId, editOrgName, editNotes, whitelistManagedExternally]);
+  }, [editingId, editOrgName, editNotes, entries, whitelistManagedExternally]);
 
   const handlePasswordChange = useCallback(async () => {
     if (passwordManagedExternally) {
@@ -253,6 +263,7 @@ export default function AdminPage() {
     }
 
     const result = await changePassword(currentPassword, newPassword);
+    void recordAuditEvent("admin_password_change", result.success ? "success" : "failure");
 
     if (result.success) {
       setPasswordSuccess("パスワードを変更しました");
@@ -310,9 +321,16 @@ export default function AdminPage() {
         allowChatFileAttachment: featureRestrictions.allowChatFileAttachment,
       });
       setFeatureRestrictions(updated);
+      void recordAuditEvent("feature_restrictions_save", "success", {
+        allowWebSearch: updated.allowWebSearch,
+        allowVectorStore: updated.allowVectorStore,
+        allowFileUpload: updated.allowFileUpload,
+        allowChatFileAttachment: updated.allowChatFileAttachment,
+      });
       setRestrictionsSuccess("機能制限の設定を保存しました");
       setTimeout(() => setRestrictionsSuccess(null), 3000);
     } catch (error) {
+      void recordAuditEvent("feature_restrictions_save", "failure");
       setError("機能制限設定の保存に失敗しました");
     }
   }, [featureRestrictions, featureRestrictionsManaged]);
@@ -7,6 +7,7 @@ import {
   getDefaultPassword,
   isPasswordChanged,
 } from "@/lib/settings/admin-password";
+import { recordAuditEvent } from "@/lib/security/audit-log";
 import "./password-gate.css";
 
 interface PasswordGateProps {
@@ -63,6 +64,7 @@ export function PasswordGate({ children }: PasswordGateProps) {
     }
 
     const isValid = await verifyPassword(password.trim());
+    void recordAuditEvent("admin_password_verify", isValid ? "success" : "failure");
 
     if (isValid) {
       setAuthenticated(true);
@@ -0,0 +1,25 @@
+import { isTauriEnvironment } from "@/lib/utils/tauri-helpers";
+
+export type AuditResult = "success" | "failure";
+
+/**
+ * 管理操作を監査ログ（audit.log）に記録する
+ * パスワードやハッシュは渡さないこと（Rust 側でも機密キーは除外される）。
+ * 記録に失敗しても操作自体は止めない。
+ */
+export async function recordAuditEvent(
+  action: string,
+  result: AuditResult,
+  params?: Record<string, unknown>,
+): Promise<void> {
+  if (!isTauriEnvironment()) {
+    return;
+  }
+
+  try {
+    const { invoke } = await import("@tauri-apps/api/core");
+    await invoke("record_audit_event", { action, result, params: params ?? null });
+  } catch (error) {
+    console.error("[AuditLog] Failed to record audit event:", action, error);
+  }
+}
@@ -3,6 +3,8 @@
  * 管理者パスワードの管理
  */
 
+import { recordAuditEvent } from "@/lib/security/audit-log";
+
 const STORAGE_KEY = "admin-password-hash";
 const DEFAULT_PASSWORD = "admin123"; // デフォルトパスワード
 
@@ -118,8 +120,10 @@ export async function resetPasswordToDefault(): Promise<void> {
   try {
     const defaultHash = await getDefaultPasswordHash();
     localStorage.setItem(STORAGE_KEY, defaultHash);
+    void recordAuditEvent("admin_password_reset", "success");
   } catch (error) {
     console.error("Failed to reset password:", error);
+    void recordAuditEvent("admin_password_reset", "failure");
     throw error;
   }
 }
@@ -1,3 +1,26 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 fn main() {
+  // 不具合報告時にビルドを特定できるよう、コミットハッシュとビルド時刻を埋め込む
+  let git_hash = Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .and_then(|output| String::from_utf8(output.stdout).ok())
+    .map(|hash| hash.trim().to_string())
+    .filter(|hash| !hash.is_empty())
+    .unwrap_or_else(|| "unknown".to_string());
+  let build_timestamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0);
+
+  println!("cargo:rustc-env=APP_GIT_HASH={}", git_hash);
+  println!("cargo:rustc-env=APP_BUILD_TIMESTAMP={}", build_timestamp);
+  println!("cargo:rerun-if-changed=../.git/HEAD");
+  println!("cargo:rerun-if-changed=../.git/refs/heads");
+
   tauri_build::build()
 }
@@ -0,0 +1,256 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+const AUDIT_LOG_FILE_NAME: &str = "audit.log";
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const MAX_ACTION_LENGTH: usize = 64;
+// パラメータ名にこれらを含む値は記録しない
+const SENSITIVE_PARAM_KEYWORDS: &[&str] = &["password", "hash", "secret", "token", "api_key", "apikey"];
+// 値をマスクして記録するパラメータ名
+const MASKED_PARAM_KEYS: &[&str] = &["identifier", "org_id", "orgid"];
+
+/// 直前のエントリのハッシュ（ファイル末尾を毎回読まないようにキャッシュする）
+static LAST_HASH: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp_ms: u64,
+    pub action: String,
+    pub result: String,
+    #[serde(default)]
+    pub params: Map<String, Value>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+fn audit_log_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|err| format!("設定フォルダの取得に失敗しました: {}", err))?;
+    Ok(config_dir.join(AUDIT_LOG_FILE_NAME))
+}
+
+/// 識別子の先頭 2 文字だけを残してマスクする
+fn mask_identifier(value: &str) -> String {
+    let prefix: String = value.chars().take(2).collect();
+    if value.chars().count() <= 2 {
+        "****".to_string()
+    } else {
+        format!("{}****", prefix)
+    }
+}
+
+fn is_sensitive_param(key: &str) -> bool {
+    let normalized = key.to_ascii_lowercase();
+    SENSITIVE_PARAM_KEYWORDS
+        .iter()
+        .any(|keyword| normalized.contains(keyword))
+}
+
+/// 入れ子のオブジェクト・配列の中も含めて、機密値を除外し識別子をマスクする
+fn sanitize_map(map: Map<String, Value>) -> Map<String, Value> {
+    map.into_iter()
+        .filter(|(key, _)| !is_sensitive_param(key))
+        .map(|(key, value)| {
+            let masked = MASKED_PARAM_KEYS.contains(&key.to_ascii_lowercase().as_str());
+            let value = match value {
+                Value::String(text) if masked => Value::String(mask_identifier(&text)),
+                other => sanitize_value(other),
+            };
+            (key, value)
+        })
+        .collect()
+}
+
+fn sanitize_value(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(sanitize_map(map)),
+        Value::Array(items) => Value::Array(items.into_iter().map(sanitize_value).collect()),
+        other => other,
+    }
+}
+
+fn sanitize_params(params: Value) -> Map<String, Value> {
+    match params {
+        Value::Object(map) => sanitize_map(map),
+        _ => Map::new(),
+    }
+}
+
+fn read_last_hash(path: &PathBuf) -> String {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return GENESIS_HASH.to_string(),
+    };
+
+    content
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .map(|entry| entry.hash)
+        .unwrap_or_else(|| GENESIS_HASH.to_string())
+}
+
+fn compute_hash(prev_hash: &str, timestamp_ms: u64, action: &str, result: &str, params: &Map<String, Value>) -> String {
+    let params_json = serde_json::to_string(params).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(timestamp_ms.to_string().as_bytes());
+    hasher.update(action.as_bytes());
+    hasher.update(result.as_bytes());
+    hasher.update(params_json.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// 監査ログ（audit.log）に 1 行追記する
+///
+/// 各行は直前の行のハッシュを含むため、途中の行を改ざん・削除するとチェーンが途切れる。
+/// パスワードやハッシュなどの機密値は `params` から除外される。
+pub fn append_audit_entry(app: &tauri::AppHandle, action: &str, result: &str, params: Value) -> Result<(), String> {
+    let path = audit_log_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("監査ログフォルダの作成に失敗しました ({}): {}", parent.display(), err))?;
+    }
+
+    let mut last_hash = LAST_HASH.lock().map_err(|_| "監査ログのロックに失敗しました".to_string())?;
+    let prev_hash = match last_hash.as_ref() {
+        Some(hash) => hash.clone(),
+        None => read_last_hash(&path),
+    };
+
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    let params = sanitize_params(params);
+    let hash = compute_hash(&prev_hash, timestamp_ms, action, result, &params);
+
+    let entry = AuditEntry {
+        timestamp_ms,
+        action: action.to_string(),
+        result: result.to_string(),
+        params,
+        prev_hash,
+        hash: hash.clone(),
+    };
+    let line = serde_json::to_string(&entry)
+        .map_err(|err| format!("監査ログの生成に失敗しました: {}", err))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|err| format!("監査ログを開けませんでした ({}): {}", path.display(), err))?;
+    writeln!(file, "{}", line)
+        .map_err(|err| format!("監査ログの書き込みに失敗しました ({}): {}", path.display(), err))?;
+
+    *last_hash = Some(hash);
+    Ok(())
+}
+
+/// 失敗してもアプリの動作を止めないよう、エラーはログ出力のみ行う
+pub fn record(app: &tauri::AppHandle, action: &str, result: &str, params: Value) {
+    if let Err(err) = append_audit_entry(app, action, result, params) {
+        log::error!("Failed to write audit log ({}): {}", action, err);
+    }
+}
+
+/// フロントエンドで実行される管理操作（パスワード検証・ホワイトリスト編集・設定保存など）を記録する
+#[tauri::command]
+pub fn record_audit_event(
+    app: tauri::AppHandle,
+    action: String,
+    result: String,
+    params: Option<Value>,
+) -> Result<(), String> {
+    let action = action.trim();
+    if action.is_empty() || action.len() > MAX_ACTION_LENGTH {
+        return Err(format!("監査アクション名が正しくありません: {}", action));
+    }
+
+    append_audit_entry(&app, action, result.trim(), params.unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sanitize_params_filters_nested_secrets_and_masks_identifiers() {
+        let params = sanitize_params(json!({
+            "password": "p",
+            "form": { "password": "p", "orgId": "org-abc", "name": "n" },
+            "entries": [{ "apiKey": "sk-1", "org_id": "org-def" }, "plain"],
+            "identifier": "ab",
+        }));
+
+        assert_eq!(
+            Value::Object(params),
+            json!({
+                "form": { "orgId": "or****", "name": "n" },
+                "entries": [{ "org_id": "or****" }, "plain"],
+                "identifier": "****",
+            })
+        );
+        assert!(sanitize_params(json!("not an object")).is_empty());
+    }
+
+    #[test]
+    fn compute_hash_chains_on_previous_hash_and_contents() {
+        let params = sanitize_params(json!({ "count": 1 }));
+        let first = compute_hash(GENESIS_HASH, 1, "action", "success", &params);
+
+        assert_eq!(first, compute_hash(GENESIS_HASH, 1, "action", "success", &params));
+        assert_eq!(first.len(), 64);
+        assert_ne!(first, compute_hash(&first, 1, "action", "success", &params));
+        assert_ne!(first, compute_hash(GENESIS_HASH, 1, "action", "failure", &params));
+        assert_ne!(first, compute_hash(GENESIS_HASH, 2, "action", "success", &params));
+    }
+
+    #[test]
+    fn read_last_hash_returns_hash_of_last_entry() {
+        let path = std::env::temp_dir().join(format!("audit-log-test-{}.log", uuid::Uuid::new_v4()));
+        assert_eq!(read_last_hash(&path), GENESIS_HASH);
+
+        let entry = |prev_hash: &str, timestamp_ms: u64| {
+            let params = Map::new();
+            AuditEntry {
+                timestamp_ms,
+                action: "action".to_string(),
+                result: "success".to_string(),
+                hash: compute_hash(prev_hash, timestamp_ms, "action", "success", &params),
+                params,
+                prev_hash: prev_hash.to_string(),
+            }
+        };
+        let first = entry(GENESIS_HASH, 1);
+        let second = entry(&first.hash, 2);
+        let content = format!(
+            "{}\n{}\n\n",
+            serde_json::to_string(&first).unwrap(),
+            serde_json::to_string(&second).unwrap()
+        );
+        fs::write(&path, content).unwrap();
+        let last_hash = read_last_hash(&path);
+
+        fs::write(&path, "not json\n").unwrap();
+        let corrupted = read_last_hash(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(last_hash, second.hash);
+        assert_eq!(corrupted, GENESIS_HASH);
+    }
+}
@@ -0,0 +1,32 @@
+use serde::Serialize;
+
+// build.rs で埋め込む値（build.rs を経由しないビルドでは "unknown" / 0 になる）
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+const GIT_HASH: Option<&str> = option_env!("APP_GIT_HASH");
+const BUILD_TIMESTAMP: Option<&str> = option_env!("APP_BUILD_TIMESTAMP");
+const USER_AGENT_PRODUCT: &str = "ai-sdk-chatui";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AppVersion {
+    pub version: String,
+    pub git_hash: String,
+    pub build_timestamp: u64, // UNIX 時刻（秒）
+}
+
+pub fn app_version() -> AppVersion {
+    AppVersion {
+        version: APP_VERSION.to_string(),
+        git_hash: GIT_HASH.unwrap_or("unknown").to_string(),
+        build_timestamp: BUILD_TIMESTAMP.and_then(|timestamp| timestamp.parse().ok()).unwrap_or(0),
+    }
+}
+
+/// リクエストに付ける既定の User-Agent（例: ai-sdk-chatui/0.1.0 (abc1234)）
+pub fn default_user_agent() -> String {
+    format!("{}/{} ({})", USER_AGENT_PRODUCT, APP_VERSION, GIT_HASH.unwrap_or("unknown"))
+}
+
+#[tauri::command]
+pub fn get_app_version() -> AppVersion {
+    app_version()
+}
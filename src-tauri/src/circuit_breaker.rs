@@ -0,0 +1,308 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+static CIRCUIT_BREAKER_CONFIG: Lazy<RwLock<CircuitBreakerConfig>> =
+    Lazy::new(|| RwLock::new(CircuitBreakerConfig::default()));
+
+/// ベースURLごとのサーキットブレーカーの状態
+static CIRCUITS: Lazy<Mutex<HashMap<String, Circuit>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// サーキットブレーカーの設定（set_circuit_breaker_config で実行時に変更できる）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32, // この回数だけ連続で失敗したら遮断する
+    #[serde(default = "default_window_ms")]
+    pub window_ms: u64, // 連続失敗を数える期間
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64, // 遮断してから試行を再開するまでの時間
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_window_ms() -> u64 {
+    60_000
+}
+
+fn default_cooldown_ms() -> u64 {
+    30_000
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            failure_threshold: default_failure_threshold(),
+            window_ms: default_window_ms(),
+            cooldown_ms: default_cooldown_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// クールダウン後、復旧確認のリクエストを 1 件だけ通している状態
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    first_failure_at: Option<Instant>,
+    opened_at: Option<Instant>,
+    probing: bool,
+}
+
+impl Circuit {
+    fn state(&self) -> CircuitState {
+        match (self.opened_at, self.probing) {
+            (None, _) => CircuitState::Closed,
+            (Some(_), true) => CircuitState::HalfOpen,
+            (Some(_), false) => CircuitState::Open,
+        }
+    }
+
+    /// 送信してよいか判定する。遮断中は再開までの残り時間を返す
+    fn try_acquire(&mut self, config: &CircuitBreakerConfig, now: Instant) -> Result<(), Duration> {
+        let opened_at = match self.opened_at {
+            Some(opened_at) => opened_at,
+            None => return Ok(()),
+        };
+
+        let cooldown = Duration::from_millis(config.cooldown_ms);
+        let elapsed = now.saturating_duration_since(opened_at);
+        if elapsed < cooldown {
+            return Err(cooldown - elapsed);
+        }
+
+        // クールダウン経過後は 1 件だけ通し、結果が返るまで（または次のクールダウンまで）他は遮断する
+        self.opened_at = Some(now);
+        self.probing = true;
+        Ok(())
+    }
+
+    /// 失敗を記録し、遮断した場合は true を返す
+    fn record_failure(&mut self, config: &CircuitBreakerConfig, now: Instant) -> bool {
+        if self.probing {
+            // 復旧確認に失敗したら再び遮断する
+            self.opened_at = Some(now);
+            self.probing = false;
+            return true;
+        }
+        if self.opened_at.is_some() {
+            return false;
+        }
+
+        let window = Duration::from_millis(config.window_ms);
+        let within_window = self
+            .first_failure_at
+            .is_some_and(|first| now.saturating_duration_since(first) <= window);
+        if within_window {
+            self.consecutive_failures += 1;
+        } else {
+            self.consecutive_failures = 1;
+            self.first_failure_at = Some(now);
+        }
+
+        if self.consecutive_failures >= config.failure_threshold.max(1) {
+            self.opened_at = Some(now);
+            return true;
+        }
+        false
+    }
+}
+
+fn current_config() -> CircuitBreakerConfig {
+    CIRCUIT_BREAKER_CONFIG.read().map(|config| *config).unwrap_or_default()
+}
+
+/// 遮断中であれば送信せずにエラーを返す
+pub fn check(base_url: &str) -> Result<(), String> {
+    let config = current_config();
+    if !config.enabled {
+        return Ok(());
+    }
+    let mut circuits = match CIRCUITS.lock() {
+        Ok(circuits) => circuits,
+        Err(_) => return Ok(()),
+    };
+    let circuit = match circuits.get_mut(base_url) {
+        Some(circuit) => circuit,
+        None => return Ok(()),
+    };
+
+    let was_open = circuit.state() == CircuitState::Open;
+    match circuit.try_acquire(&config, Instant::now()) {
+        Ok(()) => {
+            if was_open {
+                log::info!("Circuit half-open for {}, sending a probe request", base_url);
+            }
+            Ok(())
+        }
+        Err(retry_after) => Err(format!(
+            "サービスが一時的に利用できません (circuit open): {} への送信を {} 秒間停止しています",
+            base_url,
+            retry_after.as_secs().max(1)
+        )),
+    }
+}
+
+/// 応答を受け取れた（5xx 以外）ことを記録する
+pub fn record_success(base_url: &str) {
+    if let Ok(mut circuits) = CIRCUITS.lock() {
+        if let Some(circuit) = circuits.remove(base_url) {
+            if circuit.opened_at.is_some() {
+                log::info!("Circuit closed for {}", base_url);
+            }
+        }
+    }
+}
+
+/// 接続エラー・5xx を記録する
+pub fn record_failure(base_url: &str) {
+    let config = current_config();
+    if !config.enabled {
+        return;
+    }
+    if let Ok(mut circuits) = CIRCUITS.lock() {
+        let circuit = circuits.entry(base_url.to_string()).or_default();
+        if circuit.record_failure(&config, Instant::now()) {
+            log::warn!(
+                "Circuit opened for {} after {} consecutive failure(s), cooling down for {}ms",
+                base_url, circuit.consecutive_failures, config.cooldown_ms
+            );
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CircuitStatus {
+    pub base_url: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub retry_after_ms: Option<u64>, // 遮断中のみ
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CircuitBreakerSnapshot {
+    pub config: CircuitBreakerConfig,
+    pub circuits: Vec<CircuitStatus>,
+}
+
+#[tauri::command]
+pub fn get_circuit_breaker_state() -> Result<CircuitBreakerSnapshot, String> {
+    let config = current_config();
+    let circuits = CIRCUITS
+        .lock()
+        .map_err(|_| "サーキットブレーカーの状態の取得に失敗しました".to_string())?;
+
+    let cooldown = Duration::from_millis(config.cooldown_ms);
+    let mut statuses: Vec<CircuitStatus> = circuits
+        .iter()
+        .map(|(base_url, circuit)| CircuitStatus {
+            base_url: base_url.clone(),
+            state: circuit.state(),
+            consecutive_failures: circuit.consecutive_failures,
+            retry_after_ms: circuit
+                .opened_at
+                .filter(|_| !circuit.probing)
+                .map(|opened_at| cooldown.saturating_sub(opened_at.elapsed()).as_millis() as u64),
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.base_url.cmp(&b.base_url));
+
+    Ok(CircuitBreakerSnapshot { config, circuits: statuses })
+}
+
+/// 指定したベースURL（省略時はすべて）の状態をリセットし、リセットした件数を返す
+#[tauri::command]
+pub fn reset_circuit_breaker(base_url: Option<String>) -> Result<usize, String> {
+    let mut circuits = CIRCUITS
+        .lock()
+        .map_err(|_| "サーキットブレーカーのリセットに失敗しました".to_string())?;
+    let reset = match base_url.as_deref().map(str::trim) {
+        Some(base_url) => usize::from(circuits.remove(base_url).is_some()),
+        None => {
+            let count = circuits.len();
+            circuits.clear();
+            count
+        }
+    };
+    log::info!("Circuit breaker reset ({} entries)", reset);
+    Ok(reset)
+}
+
+#[tauri::command]
+pub fn set_circuit_breaker_config(config: CircuitBreakerConfig) -> Result<(), String> {
+    let mut current = CIRCUIT_BREAKER_CONFIG
+        .write()
+        .map_err(|_| "サーキットブレーカーの設定の更新に失敗しました".to_string())?;
+    *current = config;
+    log::info!("Circuit breaker config changed: {:?}", config);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 3,
+            window_ms: 60_000,
+            cooldown_ms: 30_000,
+        }
+    }
+
+    #[test]
+    fn circuit_opens_after_threshold_and_half_opens_after_cooldown() {
+        let config = config();
+        let start = Instant::now();
+        let mut circuit = Circuit::default();
+
+        assert!(!circuit.record_failure(&config, start));
+        assert!(!circuit.record_failure(&config, start));
+        assert!(circuit.record_failure(&config, start));
+        assert_eq!(circuit.state(), CircuitState::Open);
+        assert!(circuit.try_acquire(&config, start + Duration::from_secs(10)).is_err());
+
+        // クールダウン後は 1 件だけ通す
+        let after_cooldown = start + Duration::from_secs(31);
+        assert!(circuit.try_acquire(&config, after_cooldown).is_ok());
+        assert_eq!(circuit.state(), CircuitState::HalfOpen);
+        assert!(circuit.try_acquire(&config, after_cooldown).is_err());
+
+        // 復旧確認に失敗すると再び遮断される
+        assert!(circuit.record_failure(&config, after_cooldown));
+        assert_eq!(circuit.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn failures_outside_window_are_not_consecutive() {
+        let config = config();
+        let start = Instant::now();
+        let mut circuit = Circuit::default();
+
+        circuit.record_failure(&config, start);
+        circuit.record_failure(&config, start);
+        // 期間外の失敗は 1 回目として数え直す
+        assert!(!circuit.record_failure(&config, start + Duration::from_secs(61)));
+        assert_eq!(circuit.consecutive_failures, 1);
+        assert_eq!(circuit.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,88 @@
+use keyring::Entry;
+
+// OS の資格情報ストア上のサービス名（tauri.conf.json の identifier と揃える）
+const KEYCHAIN_SERVICE: &str = "com.tudoumono.ai-sdk-chatui";
+const MAX_PROFILE_NAME_LENGTH: usize = 128;
+
+fn keychain_entry(profile_name: &str) -> Result<Entry, String> {
+    let profile_name = profile_name.trim();
+    if profile_name.is_empty() || profile_name.len() > MAX_PROFILE_NAME_LENGTH {
+        return Err(format!("プロファイル名が正しくありません: {}", profile_name));
+    }
+
+    Entry::new(KEYCHAIN_SERVICE, profile_name)
+        .map_err(|err| format!("資格情報ストアにアクセスできません: {}", err))
+}
+
+/// 資格情報ストアから API キーを取得する（未登録の場合は None）
+pub fn read_api_key(profile_name: &str) -> Result<Option<String>, String> {
+    match keychain_entry(profile_name)?.get_password() {
+        Ok(api_key) => Ok(Some(api_key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(format!(
+            "API キーの取得に失敗しました ({}): {}",
+            profile_name, err
+        )),
+    }
+}
+
+/// リクエスト送信直前に、参照名から API キーを解決する
+///
+/// 資格情報ストアの API はブロッキングのため、専用スレッドで実行する。
+pub async fn resolve_api_key_ref(profile_name: &str) -> Result<String, String> {
+    let profile_name = profile_name.to_string();
+    let lookup_name = profile_name.clone();
+    let api_key = tokio::task::spawn_blocking(move || read_api_key(&lookup_name))
+        .await
+        .map_err(|err| format!("API キーの取得に失敗しました: {}", err))??;
+
+    api_key.ok_or_else(|| {
+        format!(
+            "プロファイル {} の API キーが資格情報ストアに登録されていません",
+            profile_name
+        )
+    })
+}
+
+#[tauri::command]
+pub async fn store_api_key(profile_name: String, api_key: String) -> Result<(), String> {
+    if api_key.trim().is_empty() {
+        return Err("API キーが空です".to_string());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        keychain_entry(&profile_name)?
+            .set_password(api_key.trim())
+            .map_err(|err| format!("API キーの保存に失敗しました ({}): {}", profile_name, err))?;
+        log::info!("Stored API key in OS keychain for profile {}", profile_name);
+        Ok(())
+    })
+    .await
+    .map_err(|err| format!("API キーの保存に失敗しました: {}", err))?
+}
+
+#[tauri::command]
+pub async fn load_api_key(profile_name: String) -> Result<Option<String>, String> {
+    tokio::task::spawn_blocking(move || read_api_key(&profile_name))
+        .await
+        .map_err(|err| format!("API キーの取得に失敗しました: {}", err))?
+}
+
+#[tauri::command]
+pub async fn delete_api_key(profile_name: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        match keychain_entry(&profile_name)?.delete_credential() {
+            // 未登録の場合も削除済みとして扱う
+            Ok(()) | Err(keyring::Error::NoEntry) => {
+                log::info!("Deleted API key from OS keychain for profile {}", profile_name);
+                Ok(())
+            }
+            Err(err) => Err(format!(
+                "API キーの削除に失敗しました ({}): {}",
+                profile_name, err
+            )),
+        }
+    })
+    .await
+    .map_err(|err| format!("API キーの削除に失敗しました: {}", err))?
+}
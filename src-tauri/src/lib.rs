@@ -1,11 +1,49 @@
+mod audit_log;
+mod build_info;
+mod circuit_breaker;
+mod keychain;
+mod metrics;
 mod openai_proxy;
+mod request_trace;
+mod response_cache;
+mod runtime_state;
 mod secure_config;
 
-use openai_proxy::{make_openai_request, upload_file_to_openai, OpenAIRequest, FileUploadRequest, OpenAIResponse};
+use openai_proxy::{
+  upload_file_to_openai, OpenAIRequest, FileUploadRequest, OpenAIResponse,
+  CreateUploadRequest, UploadPartRequest, CompleteUploadRequest, TranscriptionRequest, LogLimits,
+  MultiFileUploadRequest, ProxyConfig, ProxyTestResult, ProviderProfile,
+};
+use tauri::Emitter;
+use tauri_plugin_log::RotationStrategy;
 
+const DEFAULT_LOG_LEVEL: log::LevelFilter = log::LevelFilter::Info;
+const LOG_MAX_FILE_SIZE: u128 = 10 * 1024 * 1024; // 1 ファイルあたり 10MB
+const LOG_RETAINED_FILES: usize = 5;
+
+/// `progress_id` を指定した場合はボディの受信状況を `response-progress` で通知する
 #[tauri::command]
-async fn proxy_openai_request(request: OpenAIRequest) -> Result<OpenAIResponse, String> {
-    make_openai_request(request).await
+async fn proxy_openai_request(app: tauri::AppHandle, request: OpenAIRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::make_openai_request_with_progress(request, move |progress| {
+        let _ = app.emit("response-progress", progress);
+    })
+    .await
+}
+
+/// SSE のチャンクを `openai-stream-chunk`、終了（usage を含む）を `openai-stream-done` で通知する
+#[tauri::command]
+async fn proxy_openai_stream(
+    app: tauri::AppHandle,
+    request: OpenAIRequest,
+    stream_id: String,
+) -> Result<(), String> {
+    let chunk_app = app.clone();
+    let done = openai_proxy::stream_openai_request(request, stream_id, move |chunk| {
+        let _ = chunk_app.emit("openai-stream-chunk", chunk);
+    })
+    .await?;
+    let _ = app.emit("openai-stream-done", done);
+    Ok(())
 }
 
 #[tauri::command]
@@ -13,35 +51,287 @@ async fn proxy_file_upload(request: FileUploadRequest) -> Result<OpenAIResponse,
     upload_file_to_openai(request).await
 }
 
+#[tauri::command]
+async fn proxy_multi_file_upload(request: MultiFileUploadRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::upload_files_to_openai(request).await
+}
+
+#[tauri::command]
+async fn proxy_batch_input_upload(request: openai_proxy::BatchInputUploadRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::upload_batch_input(request).await
+}
+
+#[tauri::command]
+async fn create_upload(request: CreateUploadRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::create_upload(request).await
+}
+
+#[tauri::command]
+async fn upload_part(app: tauri::AppHandle, request: UploadPartRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::upload_part(request, move |progress| {
+        let _ = app.emit("upload-progress", progress);
+    })
+    .await
+}
+
+#[tauri::command]
+async fn complete_upload(request: CompleteUploadRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::complete_upload(request).await
+}
+
+#[tauri::command]
+async fn abort_upload(
+    base_url: String,
+    api_key: String,
+    upload_id: String,
+    proxy_config: Option<ProxyConfig>,
+) -> Result<openai_proxy::AbortUploadResult, String> {
+    openai_proxy::abort_upload(base_url, api_key, upload_id, proxy_config).await
+}
+
+#[tauri::command]
+async fn transcribe_audio(request: TranscriptionRequest) -> Result<OpenAIResponse, String> {
+    openai_proxy::transcribe_audio(request).await
+}
+
+/// 設定画面から API キーを使わずにプロキシの疎通を確認する
+#[tauri::command]
+async fn test_proxy_connection(
+    proxy_config: Option<ProxyConfig>,
+    test_url: Option<String>,
+) -> Result<ProxyTestResult, String> {
+    openai_proxy::test_proxy_connection(proxy_config, test_url).await
+}
+
+/// バッチが終了状態になるまでポーリングし、各回の状態を `batch-progress` で通知する
+///
+/// `group_id`（未指定時は `batch_poll:{batch_id}`）を cancel_request_group に渡すと中断できる。
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn poll_batch_status(
+    app: tauri::AppHandle,
+    base_url: String,
+    api_key: String,
+    batch_id: String,
+    interval_ms: Option<u64>,
+    timeout_ms: Option<u64>,
+    proxy_config: Option<ProxyConfig>,
+    group_id: Option<String>,
+) -> Result<OpenAIResponse, String> {
+    openai_proxy::poll_batch_status(
+        base_url,
+        api_key,
+        batch_id,
+        interval_ms,
+        timeout_ms,
+        proxy_config,
+        group_id,
+        move |progress| {
+            let _ = app.emit("batch-progress", progress);
+        },
+    )
+    .await
+}
+
+/// アイドル後の最初のリクエストが遅くならないよう接続を確立しておく（定期実行してもよい）
+#[tauri::command]
+async fn warmup_connection(
+    base_url: String,
+    proxy_config: Option<ProxyConfig>,
+) -> Result<openai_proxy::WarmupResult, String> {
+    openai_proxy::warmup_connection(base_url, proxy_config).await
+}
+
+/// API キーが有効か /models で確認する（オンボーディングの「接続テスト」用）
+#[tauri::command]
+async fn validate_api_key(
+    base_url: String,
+    api_key: String,
+    proxy_config: Option<ProxyConfig>,
+) -> Result<openai_proxy::ApiKeyValidation, String> {
+    openai_proxy::validate_api_key(base_url, api_key, proxy_config).await
+}
+
+/// UI のオンライン/オフライン表示用に、外部への経路があるかを確認する
+#[tauri::command]
+async fn check_connectivity() -> openai_proxy::ConnectivityStatus {
+    openai_proxy::check_connectivity().await
+}
+
+/// 同じ group_id で送信した実行中のリクエストをまとめてキャンセルする
+#[tauri::command]
+fn cancel_request_group(group_id: String) -> Result<usize, String> {
+    if group_id.trim().is_empty() {
+        return Err("グループIDが空です".to_string());
+    }
+    let cancelled = openai_proxy::cancel_request_group(&group_id);
+    log::info!("Cancelled {} request(s) in group {}", cancelled, group_id.trim());
+    Ok(cancelled)
+}
+
+/// ログの出力レベルを実行時に変更する（サポート対応で一時的に debug を有効化する用途）
+#[tauri::command]
+fn set_log_level(level: String) -> Result<String, String> {
+    let level_filter = match level.trim().to_ascii_lowercase().as_str() {
+        "off" => log::LevelFilter::Off,
+        "error" => log::LevelFilter::Error,
+        "warn" => log::LevelFilter::Warn,
+        "info" => log::LevelFilter::Info,
+        "debug" => log::LevelFilter::Debug,
+        "trace" => log::LevelFilter::Trace,
+        _ => return Err(format!("不明なログレベルです: {}", level)),
+    };
+
+    log::set_max_level(level_filter);
+    log::warn!("Log level changed to {}", level_filter);
+    Ok(level_filter.to_string())
+}
+
+/// ログの切り詰め文字数や大きなレスポンスの警告しきい値を変更する
+#[tauri::command]
+fn set_log_limits(limits: LogLimits) -> Result<(), String> {
+    openai_proxy::set_log_limits(limits)?;
+    log::info!("Log limits changed: {:?}", limits);
+    Ok(())
+}
+
+/// proxy_config を指定しないリクエストに適用する既定のプロキシ設定を保存する（None で解除）
+#[tauri::command]
+fn set_default_proxy_config(config: Option<ProxyConfig>) -> Result<(), String> {
+    let summary = match &config {
+        Some(config) => {
+            let summary = openai_proxy::proxy_summary(&Some(config.clone()));
+            if summary.is_empty() { "direct connection".to_string() } else { summary }
+        }
+        None => "cleared".to_string(),
+    };
+    openai_proxy::set_default_proxy_config(config)?;
+    log::info!("Default proxy configuration changed: {}", summary);
+    // 変更前のプロキシで記録した遮断状態・キャッシュを持ち越さない
+    runtime_state::reset(false)?;
+    Ok(())
+}
+
+/// 接続プールの未使用接続の保持時間・ホストごとの保持数を変更する（プロキシの接続数を抑える用途）
+#[tauri::command]
+fn set_connection_pool_config(config: openai_proxy::ConnectionPoolConfig) -> Result<(), String> {
+    openai_proxy::set_connection_pool_config(config)?;
+    log::info!("Connection pool config changed: {:?}", config);
+    Ok(())
+}
+
+/// OpenAI 互換プロバイダーごとの既定値を登録する（OpenAIRequest の provider_profile で参照する）
+#[tauri::command]
+fn register_provider_profile(name: String, profile: ProviderProfile) -> Result<(), String> {
+    openai_proxy::register_provider_profile(&name, profile)?;
+    log::info!("Provider profile registered: {}", name.trim());
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
     .setup(|app| {
-      // 開発環境と本番環境の両方でログを有効化
-      let log_level = if cfg!(debug_assertions) {
-        log::LevelFilter::Info
-      } else {
-        log::LevelFilter::Info // 本番環境でもInfoレベルのログを出力
-      };
-
+      // ロガー自体は Trace まで受け付け、実際の出力レベルは set_log_level で実行時に切り替える
+      // ログファイルはサイズ上限でローテーションし、古いものから削除する
       app.handle().plugin(
         tauri_plugin_log::Builder::default()
-          .level(log_level)
+          .level(log::LevelFilter::Trace)
+          .level_for("hyper", log::LevelFilter::Info)
+          .level_for("hyper_util", log::LevelFilter::Info)
+          .level_for("reqwest", log::LevelFilter::Info)
+          .level_for("rustls", log::LevelFilter::Info)
+          .max_file_size(LOG_MAX_FILE_SIZE)
+          .rotation_strategy(RotationStrategy::KeepSome(LOG_RETAINED_FILES))
           .build(),
       )?;
 
-      log::info!("Application started");
+      // 開発環境と本番環境の両方で Info レベルから開始
+      log::set_max_level(DEFAULT_LOG_LEVEL);
+
+      // 機能制限で止めた操作をフロントエンドに通知し、監査ログに残す
+      let handle = app.handle().clone();
+      openai_proxy::set_feature_blocked_listener(move |blocked| {
+        let _ = handle.emit("feature-blocked", blocked);
+        audit_log::record(
+          &handle,
+          "feature_blocked",
+          "blocked",
+          serde_json::json!({
+            "feature": blocked.feature,
+            "requestId": blocked.request_id,
+            "maskedKey": blocked.masked_api_key,
+            "operation": blocked.operation,
+          }),
+        );
+      });
+
+      let version = build_info::app_version();
+      log::info!(
+        "Application started (version {}, commit {}, built at {})",
+        version.version, version.git_hash, version.build_timestamp
+      );
       Ok(())
     })
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_fs::init())
     .invoke_handler(tauri::generate_handler![
       proxy_openai_request,
+      proxy_openai_stream,
       proxy_file_upload,
+      proxy_multi_file_upload,
+      proxy_batch_input_upload,
+      create_upload,
+      upload_part,
+      complete_upload,
+      abort_upload,
+      transcribe_audio,
+      test_proxy_connection,
+      validate_api_key,
+      warmup_connection,
+      check_connectivity,
+      poll_batch_status,
+      cancel_request_group,
+      set_log_level,
+      set_log_limits,
+      set_default_proxy_config,
+      set_connection_pool_config,
+      register_provider_profile,
       secure_config::load_secure_config,
       secure_config::get_config_candidates,
-      secure_config::load_secure_config_from_path
+      secure_config::load_secure_config_from_path,
+      secure_config::get_feature_restrictions,
+      secure_config::audit_config_consistency,
+      secure_config::validate_secure_config,
+      secure_config::get_effective_config_summary,
+      secure_config::sync_config_to_app_dir,
+      audit_log::record_audit_event,
+      build_info::get_app_version,
+      circuit_breaker::get_circuit_breaker_state,
+      circuit_breaker::reset_circuit_breaker,
+      circuit_breaker::set_circuit_breaker_config,
+      keychain::store_api_key,
+      keychain::load_api_key,
+      keychain::delete_api_key,
+      metrics::get_proxy_metrics,
+      metrics::reset_proxy_metrics,
+      response_cache::clear_response_cache,
+      runtime_state::reset_runtime_state,
+      request_trace::get_recent_requests,
+      request_trace::clear_recent_requests,
+      request_trace::set_request_trace_config
     ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|_app, event| {
+      if let tauri::RunEvent::ExitRequested { .. } = event {
+        // 終了時に実行中のリクエストを中断し、待機中の future でプロセスが止まらないようにする
+        let aborted = openai_proxy::abort_all_in_flight_requests();
+        log::info!("Shutdown requested: aborted {} in-flight request(s)", aborted);
+
+        // 放棄されたアップロードセッションがプロバイダー側に残らないようキャンセルする
+        let cancelled = tauri::async_runtime::block_on(openai_proxy::abort_pending_uploads());
+        log::info!("Shutdown requested: cancelled {} pending upload session(s)", cancelled);
+      }
+    });
 }
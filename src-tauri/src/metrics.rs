@@ -0,0 +1,195 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::openai_proxy::OpenAIResponse;
+
+// レイテンシ分布のバケット上限（ミリ秒）。全サンプルを保持せず近似値でパーセンタイルを求める
+const LATENCY_BUCKETS_MS: &[u64] = &[
+    10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000, 120_000, 300_000,
+];
+
+static METRICS: Lazy<Mutex<MetricsState>> = Lazy::new(|| Mutex::new(MetricsState::default()));
+
+#[derive(Debug, Default)]
+struct MetricsState {
+    total_requests: u64,
+    successes: u64,
+    client_errors: u64,
+    server_errors: u64,
+    transport_errors: u64,
+    retries: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    // 最後の要素は最大バケットを超えたサンプル
+    latency_buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProxyMetrics {
+    pub total_requests: u64,
+    pub successes: u64,
+    pub client_errors: u64,
+    pub server_errors: u64,
+    pub transport_errors: u64,
+    pub retries: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub latency_p50_ms: Option<u64>,
+    pub latency_p95_ms: Option<u64>,
+}
+
+impl MetricsState {
+    fn record_latency(&mut self, latency: Duration) {
+        let latency_ms = latency.as_millis() as u64;
+        let index = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|upper| latency_ms <= *upper)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.latency_buckets[index] += 1;
+    }
+
+    /// 該当サンプルが含まれるバケットの上限値を返す（最大バケット超過時は最大値）
+    fn percentile(&self, percentile: f64) -> Option<u64> {
+        let total: u64 = self.latency_buckets.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        let target = ((total as f64) * percentile).ceil().max(1.0) as u64;
+        let mut cumulative = 0;
+        for (index, count) in self.latency_buckets.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= target {
+                let bucket = index.min(LATENCY_BUCKETS_MS.len() - 1);
+                return Some(LATENCY_BUCKETS_MS[bucket]);
+            }
+        }
+        LATENCY_BUCKETS_MS.last().copied()
+    }
+
+    fn snapshot(&self) -> ProxyMetrics {
+        ProxyMetrics {
+            total_requests: self.total_requests,
+            successes: self.successes,
+            client_errors: self.client_errors,
+            server_errors: self.server_errors,
+            transport_errors: self.transport_errors,
+            retries: self.retries,
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+            latency_p50_ms: self.percentile(0.50),
+            latency_p95_ms: self.percentile(0.95),
+        }
+    }
+}
+
+/// リクエスト 1 件分の結果を集計に反映する
+pub fn record_request(result: &Result<OpenAIResponse, String>, bytes_sent: u64, latency: Duration) {
+    let outcome = result
+        .as_ref()
+        .ok()
+        .map(|response| (response.status, response.body.len() as u64));
+    record_outcome(outcome, bytes_sent, latency);
+}
+
+/// ステータスと受信バイト数で結果を反映する（応答がなければ `None`、ストリーミングなど OpenAIResponse を返さない場合用）
+pub fn record_outcome(outcome: Option<(u16, u64)>, bytes_sent: u64, latency: Duration) {
+    let mut state = match METRICS.lock() {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+
+    state.total_requests += 1;
+    state.bytes_sent += bytes_sent;
+    state.record_latency(latency);
+
+    match outcome {
+        Some((status, bytes_received)) => {
+            state.bytes_received += bytes_received;
+            match status {
+                500..=599 => state.server_errors += 1,
+                400..=499 => state.client_errors += 1,
+                _ => state.successes += 1,
+            }
+        }
+        None => state.transport_errors += 1,
+    }
+}
+
+/// 再送が発生したことを記録する
+pub fn record_retry() {
+    if let Ok(mut state) = METRICS.lock() {
+        state.retries += 1;
+    }
+}
+
+#[tauri::command]
+pub fn get_proxy_metrics() -> Result<ProxyMetrics, String> {
+    METRICS
+        .lock()
+        .map(|state| state.snapshot())
+        .map_err(|_| "メトリクスの取得に失敗しました".to_string())
+}
+
+#[tauri::command]
+pub fn reset_proxy_metrics() -> Result<(), String> {
+    let mut state = METRICS
+        .lock()
+        .map_err(|_| "メトリクスのリセットに失敗しました".to_string())?;
+    *state = MetricsState::default();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(latencies_ms: &[u64]) -> MetricsState {
+        let mut state = MetricsState::default();
+        for latency in latencies_ms {
+            state.record_latency(Duration::from_millis(*latency));
+        }
+        state
+    }
+
+    #[test]
+    fn percentile_is_none_without_samples() {
+        let state = MetricsState::default();
+        assert_eq!(state.percentile(0.50), None);
+        assert_eq!(state.percentile(0.95), None);
+    }
+
+    #[test]
+    fn single_sample_reports_its_bucket_upper_bound() {
+        let state = state_with(&[42]);
+        assert_eq!(state.percentile(0.0), Some(50));
+        assert_eq!(state.percentile(0.50), Some(50));
+        assert_eq!(state.percentile(1.0), Some(50));
+    }
+
+    #[test]
+    fn percentile_walks_buckets_cumulatively() {
+        // 10ms 以下が 50 件、100ms 以下が 45 件、1 秒以下が 5 件
+        let mut latencies = vec![5; 50];
+        latencies.extend(vec![80; 45]);
+        latencies.extend(vec![700; 5]);
+        let state = state_with(&latencies);
+
+        assert_eq!(state.percentile(0.50), Some(10));
+        assert_eq!(state.percentile(0.51), Some(100));
+        assert_eq!(state.percentile(0.95), Some(100));
+        assert_eq!(state.percentile(0.96), Some(1_000));
+        // バケットの上限ちょうどはそのバケットに入る
+        assert_eq!(state_with(&[250]).percentile(0.50), Some(250));
+    }
+
+    #[test]
+    fn samples_over_the_largest_bucket_report_the_largest_bound() {
+        let state = state_with(&[600_000, 900_000]);
+        assert_eq!(state.latency_buckets[LATENCY_BUCKETS_MS.len()], 2);
+        assert_eq!(state.percentile(0.50), Some(300_000));
+        assert_eq!(state.percentile(0.95), Some(300_000));
+    }
+}
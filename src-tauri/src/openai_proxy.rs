@@ -1,14 +1,29 @@
-use reqwest::{Client, Proxy, Url, multipart};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Body, Client, Proxy, Url, multipart};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::time::Instant;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use tokio_util::sync::CancellationToken;
+
+use crate::build_info;
+use crate::circuit_breaker;
+use crate::keychain;
+use crate::metrics;
+use crate::request_trace::{self, RecentRequest};
+use crate::response_cache;
+use crate::secure_config::{self, RedactionMode, ResponseRedactionConfig, SecureFeatureRestrictions};
 use uuid::Uuid;
 use base64::{Engine as _, engine::general_purpose};
 use once_cell::sync::Lazy;
 
-const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+// Authorization は追加ヘッダーで上書きできる（独自の認証方式を使う互換エンドポイント向け）
 const STRICT_FORBIDDEN_HEADERS: &[&str] = &[
-    "authorization",
     "proxy-authorization",
     "content-length",
     "connection",
@@ -20,6 +35,20 @@ const STRICT_FORBIDDEN_HEADERS: &[&str] = &[
     "expect",
 ];
 const FORBIDDEN_HEADER_PREFIXES: &[&str] = &["sec-", "proxy-"];
+// Files API の purpose として受け付ける値（allow_unknown_purpose で未知の値も許可できる）
+const KNOWN_UPLOAD_PURPOSES: &[&str] = &["assistants", "batch", "fine-tune", "vision", "user_data"];
+const MAX_UPLOAD_PART_SIZE: u64 = 64 * 1024 * 1024; // Uploads API のパート上限（64MB）
+const UPLOAD_PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+const ABORT_UPLOAD_TIMEOUT_MS: u64 = 10_000;
+const SHUTDOWN_ABORT_UPLOADS_TIMEOUT_MS: u64 = 5_000; // 終了時のキャンセル処理全体の上限
+const MAX_TRANSPORT_RETRIES: u32 = 1;
+// 単発アップロードの既定タイムアウト（接続確立 / 応答受信までの全体）
+const DEFAULT_UPLOAD_CONNECT_TIMEOUT_MS: u64 = 30_000;
+const DEFAULT_UPLOAD_TIMEOUT_MS: u64 = 30 * 60 * 1000;
+// プロキシ疎通確認の既定の接続先とタイムアウト
+const DEFAULT_PROXY_TEST_URL: &str = "https://api.openai.com/v1/models";
+const PROXY_TEST_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_MAX_UPLOAD_BYTES: u64 = 512 * 1024 * 1024; // 512MB
 
 static ALLOWED_HOSTS: Lazy<HashSet<String>> = Lazy::new(|| {
     let mut hosts: HashSet<String> = DEFAULT_ALLOWED_HOSTS
@@ -36,11 +65,173 @@ static ALLOWED_HOSTS: Lazy<HashSet<String>> = Lazy::new(|| {
         }
     }
 
+    // テストのモックサーバー（127.0.0.1）にだけ接続できるようにする
+    if cfg!(test) {
+        hosts.insert("127.0.0.1".to_string());
+    }
+
     hosts
 });
 
 const DEFAULT_ALLOWED_HOSTS: &[&str] = &["api.openai.com"];
 
+/// 実行中リクエストのキャンセル用トークン（終了時・グループ単位で中断するため）
+static IN_FLIGHT_REQUESTS: Lazy<Mutex<HashMap<Uuid, InFlightRequest>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct InFlightRequest {
+    token: CancellationToken,
+    group_id: Option<String>,
+}
+
+/// スコープを抜けたときに実行中リストから登録を外す
+struct InFlightGuard {
+    request_id: Uuid,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut requests) = IN_FLIGHT_REQUESTS.lock() {
+            requests.remove(&self.request_id);
+        }
+    }
+}
+
+fn register_in_flight(request_id: Uuid, group_id: Option<String>) -> (CancellationToken, InFlightGuard) {
+    let token = CancellationToken::new();
+    if let Ok(mut requests) = IN_FLIGHT_REQUESTS.lock() {
+        requests.insert(request_id, InFlightRequest { token: token.clone(), group_id });
+    }
+    (token, InFlightGuard { request_id })
+}
+
+/// 実行中のリクエストとして登録し、キャンセルされた場合は future を破棄して中断する
+///
+/// future を破棄すると保持している接続やリソースもすべて解放される。
+async fn run_cancellable<T, F>(request_id: Uuid, future: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    run_cancellable_in_group(request_id, None, future).await
+}
+
+/// `group_id` を付けて登録し、cancel_request_group でまとめて中断できるようにする
+async fn run_cancellable_in_group<T, F>(request_id: Uuid, group_id: Option<String>, future: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    let group_id = group_id
+        .map(|group_id| group_id.trim().to_string())
+        .filter(|group_id| !group_id.is_empty());
+    let (token, _guard) = register_in_flight(request_id, group_id);
+    tokio::select! {
+        result = future => result,
+        _ = token.cancelled() => {
+            let err_msg = format!("[Request {}] Request cancelled", request_id);
+            log::warn!("{}", err_msg);
+            Err(err_msg)
+        }
+    }
+}
+
+/// 実行中のすべてのリクエストを中断し、中断した件数を返す
+pub fn abort_all_in_flight_requests() -> usize {
+    let requests = match IN_FLIGHT_REQUESTS.lock() {
+        Ok(requests) => requests,
+        Err(_) => return 0,
+    };
+    for request in requests.values() {
+        request.token.cancel();
+    }
+    requests.len()
+}
+
+/// 指定したグループの実行中リクエストをすべて中断し、中断した件数を返す
+///
+/// 完了済みのリクエストは登録が外れているため影響しない。
+pub fn cancel_request_group(group_id: &str) -> usize {
+    let group_id = group_id.trim();
+    let requests = match IN_FLIGHT_REQUESTS.lock() {
+        Ok(requests) => requests,
+        Err(_) => return 0,
+    };
+    let mut cancelled = 0;
+    for (request_id, request) in requests.iter() {
+        if request.group_id.as_deref() == Some(group_id) {
+            log::info!("[Request {}] Cancelling request in group {}", request_id, group_id);
+            request.token.cancel();
+            cancelled += 1;
+        }
+    }
+    cancelled
+}
+
+// ログに出すボディ・ヘッダー値の既定の最大文字数（set_log_limits で変更できる）
+const MAX_LOG_BODY_CHARS: usize = 1000;
+
+/// ログ出力に関するしきい値（set_log_limits で実行時に変更できる）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct LogLimits {
+    // 未指定の場合はレスポンスサイズ上限の 20%
+    #[serde(default)]
+    pub large_response_warning_bytes: Option<usize>,
+    #[serde(default = "default_max_log_body_chars")]
+    pub max_log_body_chars: usize,
+    // false の場合、リクエストごとの指定がなければボディの内容をログに出さない
+    #[serde(default = "default_log_response_bodies")]
+    pub log_response_bodies: bool,
+}
+
+fn default_log_response_bodies() -> bool {
+    true
+}
+
+fn default_max_log_body_chars() -> usize {
+    MAX_LOG_BODY_CHARS
+}
+
+impl Default for LogLimits {
+    fn default() -> Self {
+        Self {
+            large_response_warning_bytes: None,
+            max_log_body_chars: default_max_log_body_chars(),
+            log_response_bodies: default_log_response_bodies(),
+        }
+    }
+}
+
+static LOG_LIMITS: Lazy<RwLock<LogLimits>> = Lazy::new(|| RwLock::new(LogLimits::default()));
+
+fn current_log_limits() -> LogLimits {
+    LOG_LIMITS.read().map(|limits| *limits).unwrap_or_default()
+}
+
+pub fn set_log_limits(limits: LogLimits) -> Result<(), String> {
+    let mut current = LOG_LIMITS
+        .write()
+        .map_err(|_| "ログ設定の更新に失敗しました".to_string())?;
+    *current = limits;
+    Ok(())
+}
+
+/// 文字境界を考慮して先頭 `max_chars` 文字に切り詰める（切り詰めた場合は true）
+///
+/// バイト位置で切ると日本語などのマルチバイト文字の途中で panic するため、必ずこちらを使う。
+fn truncate_for_log(text: &str, max_chars: usize) -> (&str, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((byte_index, _)) => (&text[..byte_index], true),
+        None => (text, false),
+    }
+}
+
+/// ボディやヘッダー値をログ用に切り詰める（上限は max_log_body_chars で共通）
+fn log_preview(text: &str) -> String {
+    match truncate_for_log(text, current_log_limits().max_log_body_chars) {
+        (preview, true) => format!("{}...(truncated, total {} bytes)", preview, text.len()),
+        (preview, false) => preview.to_string(),
+    }
+}
+
 fn allowed_hosts_list() -> String {
     let mut items: Vec<&String> = ALLOWED_HOSTS.iter().collect();
     items.sort();
@@ -62,17 +253,41 @@ fn is_forbidden_header(name: &str) -> bool {
         .any(|prefix| normalized.starts_with(prefix))
 }
 
-fn normalize_base_url(raw: &str) -> Result<String, String> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return Ok(DEFAULT_BASE_URL.to_string());
+fn is_loopback_host(url: &Url) -> bool {
+    let host = match url.host_str() {
+        Some(host) => host,
+        None => return false,
+    };
+
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
     }
 
-    let url = Url::parse(trimmed)
-        .map_err(|_| format!("ベースURLが正しくありません: {}", trimmed))?;
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(false)
+}
 
-    if url.scheme() != "https" {
-        return Err("HTTPS の URL のみ使用できます".to_string());
+/// スキーム・認証情報・ホストを検証する（ベースURLと絶対URLの共通チェック）
+fn validate_target_url(url: &Url, allow_insecure_http: bool) -> Result<(), String> {
+    // ローカルの開発用プロキシ（localhost / 127.0.0.1）は HTTP を自動的に許可（ホストの許可リストは他と同様に適用する）
+    let loopback = is_loopback_host(url);
+    match url.scheme() {
+        "https" => {}
+        "http" if allow_insecure_http || loopback => {
+            log::warn!("Insecure HTTP URL in use: {}", url);
+        }
+        "http" => {
+            return Err(
+                "HTTP では API キーが平文で送信されるため使用できません。HTTPS の URL を指定してください"
+                    .to_string(),
+            );
+        }
+        scheme => {
+            return Err(format!("サポートされていないスキームです: {}", scheme));
+        }
     }
 
     if !url.username().is_empty() || url.password().is_some() {
@@ -92,6 +307,20 @@ fn normalize_base_url(raw: &str) -> Result<String, String> {
         ));
     }
 
+    Ok(())
+}
+
+fn normalize_base_url(raw: &str, allow_insecure_http: bool) -> Result<String, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("ベースURLが指定されていません".to_string());
+    }
+
+    let url = Url::parse(trimmed)
+        .map_err(|_| format!("ベースURLが正しくありません: {}", trimmed))?;
+
+    validate_target_url(&url, allow_insecure_http)?;
+
     if url.query().is_some() {
         return Err("クエリ文字列を含む URL は使用できません".to_string());
     }
@@ -110,389 +339,5181 @@ fn normalize_base_url(raw: &str) -> Result<String, String> {
     Ok(normalized)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn is_absolute_url(path: &str) -> bool {
+    let lower = path.trim_start().to_ascii_lowercase();
+    lower.starts_with("https://") || lower.starts_with("http://")
+}
+
+/// リクエストURLを構築する。`path` が絶対URLの場合は結合せずそのまま使用する
+/// raw_url が指定されていれば末尾・先頭のスラッシュ除去や結合を行わずにそのまま使う
+///
+/// raw_url でもスキーム・ホストの検証は通常どおり行う。
+fn resolve_request_url(request: &OpenAIRequest, allow_insecure_http: bool) -> Result<String, String> {
+    let url = match request.raw_url.as_deref().filter(|raw_url| !raw_url.trim().is_empty()) {
+        Some(raw_url) => {
+            let url = Url::parse(raw_url).map_err(|_| format!("リクエストURLが正しくありません: {}", raw_url))?;
+            validate_target_url(&url, allow_insecure_http)?;
+            raw_url.to_string()
+        }
+        None => build_request_url(&request.base_url, &request.path, allow_insecure_http)?,
+    };
+    apply_query_params(&url, &request.query)
+}
+
+/// クエリパラメータを URL エンコードして付与する
+///
+/// URL に同名のパラメータがあれば `query` の値で置き換える。`query` が空なら URL はそのまま。
+fn apply_query_params(url: &str, query: &Option<HashMap<String, String>>) -> Result<String, String> {
+    let query = match query.as_ref().filter(|query| !query.is_empty()) {
+        Some(query) => query,
+        None => return Ok(url.to_string()),
+    };
+
+    let mut parsed = Url::parse(url).map_err(|_| format!("リクエストURLが正しくありません: {}", url))?;
+    let mut pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !query.contains_key(name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    let mut overrides: Vec<(&String, &String)> = query.iter().collect();
+    overrides.sort();
+    pairs.extend(overrides.into_iter().map(|(name, value)| (name.clone(), value.clone())));
+
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    Ok(parsed.to_string())
+}
+
+/// サーキットブレーカーのキー（ベースURLを使わない raw_url・絶対URLの場合はオリジン）
+fn circuit_key(request: &OpenAIRequest, url: &str, allow_insecure_http: bool) -> String {
+    let uses_raw_url = request.raw_url.as_deref().is_some_and(|raw_url| !raw_url.trim().is_empty());
+    if uses_raw_url || is_absolute_url(&request.path) {
+        return Url::parse(url)
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or_else(|_| url.to_string());
+    }
+    normalize_base_url(&request.base_url, allow_insecure_http).unwrap_or_else(|_| request.base_url.clone())
+}
+
+fn build_request_url(base_url: &str, path: &str, allow_insecure_http: bool) -> Result<String, String> {
+    if is_absolute_url(path) {
+        // 署名付きURLなどはクエリを含むため、ホスト等の検証のみ行う
+        let trimmed = path.trim();
+        let url = Url::parse(trimmed)
+            .map_err(|_| format!("リクエストURLが正しくありません: {}", trimmed))?;
+        validate_target_url(&url, allow_insecure_http)?;
+        return Ok(trimmed.to_string());
+    }
+
+    let normalized_base_url = normalize_base_url(base_url, allow_insecure_http)?;
+    let path = path.trim_start_matches('/');
+    Ok(format!("{}/{}", normalized_base_url, path))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
 pub struct ProxyConfig {
     pub http_proxy: Option<String>,
     pub https_proxy: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct OpenAIRequest {
     pub base_url: String,
+    #[serde(default)]
     pub api_key: String,
+    pub api_key_ref: Option<String>, // OS の資格情報ストアに保存したプロファイル名
     pub method: String,
     pub path: String,
     pub body: Option<serde_json::Value>,
     pub additional_headers: Option<HashMap<String, String>>,
     pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub response_header_allowlist: Option<Vec<String>>, // 大文字小文字を区別しない
+    pub idempotency_key: Option<String>,
+    pub cache_ttl_ms: Option<u64>, // GET のみ有効。0 または未指定でキャッシュしない
+    pub log_response_body: Option<bool>, // 未指定時は LogLimits の log_response_bodies に従う
+    pub content_type: Option<String>,
+    pub raw_body: Option<String>, // 指定時は body より優先し、JSON 変換せずそのまま送信する
+    pub dry_run: Option<bool>, // true の場合は送信せず、送信予定の内容を返す
+    pub overall_deadline_ms: Option<u64>, // 再送を含めた全体の上限時間
+    pub fallback_base_urls: Option<Vec<String>>, // 接続エラー・タイムアウト・5xx の場合に順に試す
+    pub use_system_certs: Option<bool>, // OS の証明書ストアのルート証明書も信頼する
+    pub group_id: Option<String>, // cancel_request_group でまとめて中断するためのグループ
+    pub progress_id: Option<String>, // 指定時はボディ受信中に response-progress を通知する
+    pub raw_url: Option<String>, // 指定時は base_url・path を結合せず、この URL をそのまま使う
+    pub max_response_bytes: Option<u64>, // レスポンスサイズの上限（既定は 50MB、最大 1GB）
+    pub query: Option<HashMap<String, String>>, // URL エンコードして付与する（path 内の同名パラメータより優先）
+    pub auth_header: Option<String>, // API キーを送るヘッダー名（未指定時は Authorization: Bearer）
+    pub provider_profile: Option<String>, // register_provider_profile で登録した既定値を適用する
+    pub http2_prior_knowledge: Option<bool>, // HTTP/2 を ALPN で通知しないゲートウェイ向け（HTTPS のみ）
+    pub min_tls_version: Option<String>, // 許可する最小の TLS バージョン（"1.0"〜"1.3"）
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FileUploadRequest {
-    pub base_url: String,
-    pub api_key: String,
-    pub file_data: String, // Base64 encoded file data
-    pub file_name: String,
-    pub purpose: String,
-    pub additional_headers: Option<HashMap<String, String>>,
-    pub proxy_config: Option<ProxyConfig>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OpenAIResponse {
-    pub status: u16,
-    pub body: String,
-    pub headers: HashMap<String, String>,
+/// OpenAI 互換プロバイダーごとの既定値（リクエストで指定した項目が優先される）
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ProviderProfile {
+    pub base_url: Option<String>, // リクエストの base_url が空の場合に使う
+    pub auth_header: Option<String>, // 例: Azure OpenAI は "api-key"
+    pub headers: Option<HashMap<String, String>>,
+    pub api_version: Option<String>, // `api-version` クエリとして付与する
 }
 
-pub async fn make_openai_request(request: OpenAIRequest) -> Result<OpenAIResponse, String> {
-    // リクエストIDを生成
-    let request_id = Uuid::new_v4();
-    let start_time = Instant::now();
-
-    log::info!("[Request {}] Starting new request", request_id);
-
-    // クライアントビルダーを作成
-    let mut client_builder = Client::builder();
+/// 登録済みのプロバイダープロファイル（名前 → 既定値）
+static PROVIDER_PROFILES: Lazy<RwLock<HashMap<String, ProviderProfile>>> = Lazy::new(|| RwLock::new(HashMap::new()));
 
-    // プロキシ設定があれば適用
-    let mut proxy_info = String::new();
-    if let Some(proxy_config) = &request.proxy_config {
-        if let Some(http_proxy) = &proxy_config.http_proxy {
-            if !http_proxy.is_empty() {
-                log::info!("[Request {}] Setting HTTP proxy: {}", request_id, http_proxy);
-                proxy_info.push_str(&format!("HTTP Proxy: {}, ", http_proxy));
-                let proxy = Proxy::http(http_proxy)
-                    .map_err(|e| {
-                        let err_msg = format!("[Request {}] HTTP proxy configuration error: {} (Proxy: {})", request_id, e, http_proxy);
-                        log::error!("{}", err_msg);
-                        err_msg
-                    })?;
-                client_builder = client_builder.proxy(proxy);
-            }
-        }
-        if let Some(https_proxy) = &proxy_config.https_proxy {
-            if !https_proxy.is_empty() {
-                log::info!("[Request {}] Setting HTTPS proxy: {}", request_id, https_proxy);
-                proxy_info.push_str(&format!("HTTPS Proxy: {}", https_proxy));
-                let proxy = Proxy::https(https_proxy)
-                    .map_err(|e| {
-                        let err_msg = format!("[Request {}] HTTPS proxy configuration error: {} (Proxy: {})", request_id, e, https_proxy);
-                        log::error!("{}", err_msg);
-                        err_msg
-                    })?;
-                client_builder = client_builder.proxy(proxy);
-            }
+/// プロバイダープロファイルを登録する（同じ名前は上書き）
+pub fn register_provider_profile(name: &str, profile: ProviderProfile) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("プロファイル名が空です".to_string());
+    }
+    let header_names = profile.auth_header.iter().chain(profile.headers.iter().flat_map(|headers| headers.keys()));
+    for header_name in header_names {
+        if HeaderName::from_bytes(header_name.trim().as_bytes()).is_err() {
+            return Err(format!("ヘッダー名が正しくありません: {}", header_name));
         }
     }
 
-    if !proxy_info.is_empty() {
-        log::info!("[Request {}] Proxy configuration applied: {}", request_id, proxy_info);
-    } else {
-        log::info!("[Request {}] No proxy configuration, connecting directly", request_id);
-    }
+    let mut profiles = PROVIDER_PROFILES
+        .write()
+        .map_err(|_| "プロバイダープロファイルの登録に失敗しました".to_string())?;
+    profiles.insert(name.to_string(), profile);
+    Ok(())
+}
 
-    let client = client_builder
-        .build()
-        .map_err(|e| {
-            let err_msg = format!("[Request {}] Failed to build HTTP client: {}", request_id, e);
+/// provider_profile が指定されていれば、リクエストで指定していない項目にプロファイルの既定値を適用する
+fn apply_provider_profile(mut request: OpenAIRequest, request_id: &Uuid) -> Result<OpenAIRequest, String> {
+    let name = match request.provider_profile.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => return Ok(request),
+    };
+    let profile = PROVIDER_PROFILES
+        .read()
+        .ok()
+        .and_then(|profiles| profiles.get(&name).cloned())
+        .ok_or_else(|| {
+            let err_msg = format!("[Request {}] 登録されていないプロバイダープロファイルです: {}", request_id, name);
             log::error!("{}", err_msg);
             err_msg
         })?;
 
-    // URLを構築
-    let normalized_base_url = normalize_base_url(&request.base_url).map_err(|err| {
-        log::error!("[Request {}] Base URL validation failed: {}", request_id, err);
-        err
-    })?;
-    let path = request.path.trim_start_matches('/');
-    let url = format!("{}/{}", normalized_base_url, path);
-
-    // APIキーをマスクしてログ出力
-    let masked_api_key = if request.api_key.len() > 8 {
-        format!("{}...{}", &request.api_key[..4], &request.api_key[request.api_key.len()-4..])
-    } else {
-        "****".to_string()
-    };
-
-    // 追加ヘッダーの数を記録
-    let custom_headers_count = request.additional_headers.as_ref().map_or(0, |h| h.len());
+    if request.base_url.trim().is_empty() {
+        request.base_url = profile.base_url.unwrap_or_default();
+    }
+    if request.auth_header.is_none() {
+        request.auth_header = profile.auth_header;
+    }
+    if let Some(mut headers) = profile.headers {
+        // リクエストの追加ヘッダーを優先する（大文字小文字を区別せずに置き換える）
+        for (name, value) in request.additional_headers.take().into_iter().flatten() {
+            headers.retain(|existing, _| !existing.trim().eq_ignore_ascii_case(name.trim()));
+            headers.insert(name, value);
+        }
+        request.additional_headers = Some(headers);
+    }
+    if let Some(api_version) = profile.api_version {
+        // path に api-version を含めた場合もそちらを優先する
+        if !request.path.contains("api-version=") {
+            request
+                .query
+                .get_or_insert_with(HashMap::new)
+                .entry("api-version".to_string())
+                .or_insert(api_version);
+        }
+    }
 
-    // ボディサイズを計算
-    let body_size = request.body.as_ref().map_or(0, |b| {
-        serde_json::to_string(b).map(|s| s.len()).unwrap_or(0)
-    });
+    log::info!("[Request {}] Provider profile applied: {}", request_id, name);
+    Ok(request)
+}
 
-    log::info!(
-        "[Request {}] {} {} | API Key: {} | Custom Headers: {} | Body Size: {} bytes",
-        request_id, request.method, url, masked_api_key, custom_headers_count, body_size
-    );
+/// config.pkg の機能制限で止める操作
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestrictedFeature {
+    WebSearch,
+    VectorStore,
+    FileUpload,
+    ChatFileAttachment,
+}
 
-    // リクエストビルダーを作成
-    let mut req_builder = match request.method.to_uppercase().as_str() {
-        "GET" => client.get(&url),
-        "POST" => client.post(&url),
-        "PUT" => client.put(&url),
-        "DELETE" => client.delete(&url),
-        "PATCH" => client.patch(&url),
-        _ => return Err(format!("Unsupported HTTP method: {}", request.method)),
-    };
-
-    // 追加ヘッダーを設定
-    if let Some(headers) = &request.additional_headers {
-        for (key, value) in headers {
-            if is_forbidden_header(key) {
-                log::warn!("[Request {}] Forbidden header dropped: {}", request_id, key);
-                continue;
-            }
-            req_builder = req_builder.header(key, value);
+impl RestrictedFeature {
+    fn label(self) -> &'static str {
+        match self {
+            Self::WebSearch => "Web 検索",
+            Self::VectorStore => "ベクトルストア",
+            Self::FileUpload => "ファイルのアップロード",
+            Self::ChatFileAttachment => "チャットへのファイル添付",
         }
     }
 
-    // Content-Type ヘッダーを設定（JSONの場合）
-    if request.body.is_some() {
-        req_builder = req_builder.header("Content-Type", "application/json");
-    }
-
-    // ボディを設定
-    if let Some(body) = &request.body {
-        req_builder = req_builder.json(body);
+    fn is_allowed(self, restrictions: &SecureFeatureRestrictions) -> bool {
+        let allowed = match self {
+            Self::WebSearch => restrictions.allow_web_search,
+            Self::VectorStore => restrictions.allow_vector_store,
+            Self::FileUpload => restrictions.allow_file_upload,
+            Self::ChatFileAttachment => restrictions.allow_chat_file_attachment,
+        };
+        allowed.unwrap_or(true)
     }
+}
 
-    // Authorization は常に最後に設定
-    req_builder = req_builder.header("Authorization", format!("Bearer {}", request.api_key));
-
-    // リクエストを送信
-    log::info!("[Request {}] Sending request...", request_id);
-    let send_start = Instant::now();
-    let response = req_builder
-        .send()
-        .await
-        .map_err(|e| {
-            let elapsed = send_start.elapsed();
-
-            // エラー種別を詳細に分類
-            let err_msg = if e.is_connect() {
-                if e.to_string().contains("dns") || e.to_string().contains("resolve") {
-                    format!("[Request {}] DNS resolution failed: {} (Check domain name or DNS settings)", request_id, e)
-                } else if e.to_string().contains("certificate") || e.to_string().contains("ssl") || e.to_string().contains("tls") {
-                    format!("[Request {}] SSL/TLS error: {} (Check certificate validity or security settings)", request_id, e)
-                } else if e.to_string().contains("407") || e.to_string().contains("Proxy Authentication") {
-                    format!("[Request {}] Proxy authentication required: {} (Check proxy credentials)", request_id, e)
-                } else {
-                    format!("[Request {}] Connection failed: {} (Check network/proxy settings)", request_id, e)
-                }
-            } else if e.is_timeout() {
-                format!("[Request {}] Request timeout after {:?}: {}", request_id, elapsed, e)
-            } else if e.is_request() {
-                format!("[Request {}] Request error: {}", request_id, e)
-            } else if e.is_decode() {
-                format!("[Request {}] Response decode error: {}", request_id, e)
-            } else {
-                format!("[Request {}] Failed to send request: {}", request_id, e)
-            };
-            log::error!("{}", err_msg);
-            log::error!("[Request {}] Request failed after {:?}", request_id, elapsed);
-
-            // プロキシが設定されている場合は追加情報を出力
-            if !proxy_info.is_empty() {
-                log::error!("[Request {}] Active proxy configuration: {}", request_id, proxy_info);
-            }
+/// 機能制限で止めた操作の内容（`feature-blocked` イベント・監査ログ用）
+#[derive(Debug, Serialize, Clone)]
+pub struct FeatureBlocked {
+    pub feature: RestrictedFeature,
+    pub request_id: String,
+    pub masked_api_key: String,
+    pub operation: String,
+}
 
-            err_msg
-        })?;
+type FeatureBlockedListener = Arc<dyn Fn(&FeatureBlocked) + Send + Sync>;
 
-    // ステータスコードを取得
-    let status = response.status().as_u16();
-    let network_time = send_start.elapsed();
+static FEATURE_BLOCKED_LISTENER: Lazy<RwLock<Option<FeatureBlockedListener>>> = Lazy::new(|| RwLock::new(None));
 
-    // レスポンスヘッダーを取得
-    let mut headers = HashMap::new();
-    for (key, value) in response.headers() {
-        if let Ok(value_str) = value.to_str() {
-            headers.insert(key.to_string(), value_str.to_string());
-        }
+/// 機能制限で操作を止めたときの通知先を登録する（イベント送信・監査ログは呼び出し側で行う）
+pub fn set_feature_blocked_listener<F>(listener: F)
+where
+    F: Fn(&FeatureBlocked) + Send + Sync + 'static,
+{
+    if let Ok(mut current) = FEATURE_BLOCKED_LISTENER.write() {
+        *current = Some(Arc::new(listener));
     }
+}
 
-    // レスポンスボディを取得（サイズ制限付き）
-    const MAX_RESPONSE_SIZE: usize = 50 * 1024 * 1024; // 50MB制限
-    let body = response
-        .text()
-        .await
-        .map_err(|e| {
-            let err_msg = format!("[Request {}] Failed to read response body: {}", request_id, e);
-            log::error!("{}", err_msg);
-            err_msg
-        })?;
-
-    let response_size = body.len();
-    let total_time = start_time.elapsed();
-
-    // レスポンスサイズチェック
-    if response_size > MAX_RESPONSE_SIZE {
-        let err_msg = format!(
-            "[Request {}] Response too large: {} bytes (limit: {} bytes)",
-            request_id, response_size, MAX_RESPONSE_SIZE
-        );
-        log::error!("{}", err_msg);
-        return Err(err_msg);
+/// 機能が制限されていれば通知してエラーを返す（クライアント作成前に呼び、接続を使わない）
+///
+/// `restrictions` には通常 `secure_config::active_feature_restrictions()` を渡す。
+/// `key_identifier` はマスク済みの API キー、または資格情報ストアのプロファイル名。
+fn enforce_feature(
+    feature: RestrictedFeature,
+    restrictions: &SecureFeatureRestrictions,
+    key_identifier: &str,
+    operation: &str,
+    request_id: &Uuid,
+) -> Result<(), String> {
+    if feature.is_allowed(restrictions) {
+        return Ok(());
     }
 
-    // ログ出力
-    log::info!(
-        "[Request {}] Response received | Status: {} | Size: {} bytes | Network: {:?} | Total: {:?}",
-        request_id, status, response_size, network_time, total_time
+    let blocked = FeatureBlocked {
+        feature,
+        request_id: request_id.to_string(),
+        masked_api_key: key_identifier.to_string(),
+        operation: operation.to_string(),
+    };
+    let err_msg = format!(
+        "[Request {}] 管理者により「{}」が無効化されているため実行できません",
+        request_id,
+        feature.label()
+    );
+    log::warn!(
+        "[Request {}] Blocked by feature restrictions: {:?} | Operation: {} | API Key: {}",
+        request_id, feature, operation, blocked.masked_api_key
     );
 
-    // レスポンスボディをログに出力（デバッグ用）
-    if request.path.contains("/responses") {
-        let body_preview = if body.len() > 1000 {
-            format!("{}...(truncated)", &body[..1000])
-        } else {
-            body.clone()
-        };
-        log::info!("[Request {}] Response body: {}", request_id, body_preview);
-    }
-
-    // 大きなレスポンスの警告
-    if response_size > 10 * 1024 * 1024 {
-        log::warn!("[Request {}] Large response detected: {} MB", request_id, response_size / 1024 / 1024);
+    let listener = FEATURE_BLOCKED_LISTENER.read().ok().and_then(|listener| listener.clone());
+    if let Some(listener) = listener {
+        listener(&blocked);
     }
+    Err(err_msg)
+}
 
-    // エラーレスポンスの場合はログに出力
-    if status >= 400 {
-        // エラーボディを省略表示（長すぎる場合）
-        let body_preview = if body.len() > 500 {
-            format!("{}... (truncated, total {} bytes)", &body[..500], body.len())
-        } else {
-            body.clone()
-        };
-        log::error!("[Request {}] OpenAI API error ({}): {}", request_id, status, body_preview);
-    } else {
-        log::info!("[Request {}] Request completed successfully", request_id);
+/// メッセージにファイルのコンテンツ（Responses API の input_file、Chat Completions の file）を含むか
+fn contains_file_part(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Array(items) => items.iter().any(contains_file_part),
+        serde_json::Value::Object(map) => {
+            matches!(map.get("type").and_then(|kind| kind.as_str()), Some("input_file") | Some("file"))
+                || map.get("content").is_some_and(contains_file_part)
+        }
+        _ => false,
     }
-
-    Ok(OpenAIResponse {
-        status,
-        body,
-        headers,
-    })
 }
 
-pub async fn upload_file_to_openai(request: FileUploadRequest) -> Result<OpenAIResponse, String> {
-    let request_id = Uuid::new_v4();
-    let start_time = Instant::now();
+/// 送信先のパスの各セグメント（raw_url・絶対 URL の path も URL のパス部分で判定する）
+fn request_path_segments(request: &OpenAIRequest) -> Vec<String> {
+    let target = request
+        .raw_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|raw_url| !raw_url.is_empty())
+        .unwrap_or_else(|| request.path.trim());
+    let path = match Url::parse(target) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => target.split(['?', '#']).next().unwrap_or_default().to_string(),
+    };
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
 
-    log::info!("[Request {}] Starting file upload: {}", request_id, request.file_name);
+/// リクエストが使う制限対象の機能（パスとボディの tools・添付ファイルから判定する）
+fn requested_features(request: &OpenAIRequest) -> Vec<RestrictedFeature> {
+    let mut features = Vec::new();
+    let mut add = |feature| {
+        if !features.contains(&feature) {
+            features.push(feature);
+        }
+    };
 
-    // クライアントビルダーを作成
-    let mut client_builder = Client::builder();
+    let segments = request_path_segments(request);
+    if segments.iter().any(|segment| segment == "vector_stores") {
+        add(RestrictedFeature::VectorStore);
+    }
+    // /files・/uploads への書き込みは汎用のリクエスト経由でもアップロードとして扱う（一覧・取得・削除は対象外）
+    let method = request.method.trim().to_ascii_uppercase();
+    let writes = !matches!(method.as_str(), "GET" | "HEAD" | "DELETE");
+    if writes && segments.iter().any(|segment| segment == "files" || segment == "uploads") {
+        add(RestrictedFeature::FileUpload);
+    }
 
-    // プロキシ設定があれば適用
-    if let Some(proxy_config) = &request.proxy_config {
-        if let Some(http_proxy) = &proxy_config.http_proxy {
-            if !http_proxy.is_empty() {
-                let proxy = Proxy::http(http_proxy)
-                    .map_err(|e| format!("[Request {}] HTTP proxy error: {}", request_id, e))?;
-                client_builder = client_builder.proxy(proxy);
+    let raw_body = request
+        .raw_body
+        .as_deref()
+        .and_then(|raw_body| serde_json::from_str::<serde_json::Value>(raw_body).ok());
+    let body = match &request.raw_body {
+        Some(_) => raw_body.as_ref(),
+        None => request.body.as_ref(),
+    };
+    if let Some(body) = body {
+        let tools = body.get("tools").and_then(|tools| tools.as_array());
+        for tool_type in tools.into_iter().flatten().filter_map(|tool| tool.get("type")?.as_str()) {
+            if tool_type.starts_with("web_search") {
+                add(RestrictedFeature::WebSearch);
+            } else if tool_type == "file_search" {
+                add(RestrictedFeature::VectorStore);
             }
         }
-        if let Some(https_proxy) = &proxy_config.https_proxy {
-            if !https_proxy.is_empty() {
-                let proxy = Proxy::https(https_proxy)
-                    .map_err(|e| format!("[Request {}] HTTPS proxy error: {}", request_id, e))?;
-                client_builder = client_builder.proxy(proxy);
-            }
+        if body.get("web_search_options").is_some() {
+            add(RestrictedFeature::WebSearch);
+        }
+        if ["input", "messages"].iter().filter_map(|key| body.get(key)).any(contains_file_part) {
+            add(RestrictedFeature::ChatFileAttachment);
         }
     }
+    features
+}
 
-    let client = client_builder
-        .build()
-        .map_err(|e| format!("[Request {}] Failed to build HTTP client: {}", request_id, e))?;
+fn enforce_request_features(
+    request: &OpenAIRequest,
+    restrictions: &SecureFeatureRestrictions,
+    request_id: &Uuid,
+) -> Result<(), String> {
+    let operation = format!("{} {}", request.method.to_uppercase(), request.raw_url.as_deref().unwrap_or(&request.path));
+    for feature in requested_features(request) {
+        enforce_feature(feature, restrictions, &request_key_identifier(request), &operation, request_id)?;
+    }
+    Ok(())
+}
 
-    // Base64デコード
-    let file_bytes = general_purpose::STANDARD
-        .decode(&request.file_data)
-        .map_err(|e| format!("[Request {}] Base64 decode error: {}", request_id, e))?;
+/// 通知・監査ログに残すキーの識別子（資格情報ストアを参照する場合は api_key が空のためプロファイル名を使う）
+fn request_key_identifier(request: &OpenAIRequest) -> String {
+    match request.api_key_ref.as_deref().map(str::trim) {
+        Some(profile_name) if !profile_name.is_empty() => format!("profile:{}", profile_name),
+        _ => mask_api_key(&request.api_key),
+    }
+}
 
-    log::info!("[Request {}] File size: {} bytes", request_id, file_bytes.len());
+impl OpenAIRequest {
+    /// 送信するボディのバイト数（raw_body を優先）
+    fn body_len(&self) -> usize {
+        match (&self.raw_body, &self.body) {
+            (Some(raw_body), _) => raw_body.len(),
+            (None, Some(body)) => serde_json::to_vec(body).map(|v| v.len()).unwrap_or(0),
+            (None, None) => 0,
+        }
+    }
+}
 
-    // URLを構築
-    let normalized_base_url = normalize_base_url(&request.base_url).map_err(|err| {
-        log::error!("[Request {}] Base URL validation failed: {}", request_id, err);
-        err
-    })?;
-    let url = format!("{}/files", normalized_base_url);
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileUploadRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub file_data: String, // Base64 encoded file data
+    pub file_name: String,
+    pub purpose: String,
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub max_upload_bytes: Option<u64>, // 既定は 512MB
+    pub connect_timeout_ms: Option<u64>, // 既定は 30 秒
+    pub upload_timeout_ms: Option<u64>,  // 送信〜応答受信までの全体。既定は 30 分
+    pub use_system_certs: Option<bool>,
+    pub allow_unknown_purpose: Option<bool>, // 新しい purpose に対応するため未知の値も送信する
+    pub gzip_before_upload: Option<bool>, // gzip 圧縮した入力を受け付けるエンドポイント・purpose でのみ指定する
+    pub min_tls_version: Option<String>, // 許可する最小の TLS バージョン（"1.0"〜"1.3"）
+}
 
-    // multipart/form-data を作成
-    let file_part = multipart::Part::bytes(file_bytes)
-        .file_name(request.file_name.clone())
-        .mime_str("application/octet-stream")
-        .map_err(|e| format!("[Request {}] Failed to create file part: {}", request_id, e))?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OpenAIResponse {
+    pub status: u16,
+    pub body: String,
+    pub headers: HashMap<String, String>,
+    pub usage: Option<TokenUsage>,
+    pub error_details: Option<ApiErrorDetails>, // 4xx/5xx で JSON の `error` を含む場合のみ
+    pub auth_error: Option<AuthError>, // 401/403 のときのみ
+    #[serde(default)]
+    pub dry_run: bool, // true の場合は送信していないプレビュー（status は 0）
+    #[serde(default)]
+    pub served_by: Option<String>, // 応答したベースURL（フォールバック時に UI で警告する用途）
+    #[serde(default)]
+    pub timing: Option<RequestTiming>, // 実際に送信した場合のみ
+    #[serde(default)]
+    pub remote_addr: Option<String>, // 接続先の IP アドレスとポート（プロキシ経由の場合はプロキシのアドレス）
+    #[serde(default)]
+    pub attempts: u32, // 再送・フォールバックを含む送信回数（キャッシュヒット・プレビューは 0）
+}
 
-    let form = multipart::Form::new()
-        .part("file", file_part)
-        .text("purpose", request.purpose.clone());
+/// リクエストの所要時間の内訳（ミリ秒）
+///
+/// DNS 解決・接続確立の時間は reqwest から取得できないため、`ttfb_ms` に含まれる。
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct RequestTiming {
+    pub setup_ms: u64,              // URL 検証・クライアント作成・API キー解決など送信前の準備
+    pub queue_wait_ms: Option<u64>, // 同時実行数の制限による待ち時間（制限がない場合は None）
+    pub ttfb_ms: Option<u64>,       // 送信開始からレスポンスヘッダー受信まで（再送を含む）
+    pub download_ms: Option<u64>,   // ボディの受信
+    pub total_ms: u64,              // フォールバックを含む全体
+}
 
-    // リクエストを送信
-    log::info!("[Request {}] Uploading to {}", request_id, url);
-    let mut req_builder = client.post(&url)
-        .multipart(form);
-
-    // 追加ヘッダーを設定
-    if let Some(headers) = &request.additional_headers {
-        for (key, value) in headers {
-            if is_forbidden_header(key) {
-                log::warn!("[Request {}] Forbidden header dropped: {}", request_id, key);
-                continue;
-            }
-            req_builder = req_builder.header(key, value);
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// 認証・認可エラーの種別（フロントエンドで API キーの再入力を促すのに使う）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthError {
+    /// 401: API キーが無効・期限切れ
+    InvalidCredentials,
+    /// 403: キーは有効だが権限がない（組織・プロジェクト・リージョン制限など）
+    Forbidden,
+}
+
+impl AuthError {
+    fn from_status(status: u16) -> Option<Self> {
+        match status {
+            401 => Some(AuthError::InvalidCredentials),
+            403 => Some(AuthError::Forbidden),
+            _ => None,
         }
     }
+}
 
-    // Authorization は常に最後に設定
-    req_builder = req_builder.header("Authorization", format!("Bearer {}", request.api_key));
+/// エラーレスポンスの `error` オブジェクト（`{"error": {"type", "code", "message", "param"}}`）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ApiErrorDetails {
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub param: Option<String>,
+}
 
-    let send_start = Instant::now();
-    let response = req_builder
-        .send()
-        .await
-        .map_err(|e| {
-            log::error!("[Request {}] Upload failed: {}", request_id, e);
-            format!("[Request {}] Failed to upload file: {}", request_id, e)
-        })?;
+/// `usage` オブジェクト（Responses API の input/output_tokens も受け付ける）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TokenUsage {
+    #[serde(default, alias = "input_tokens")]
+    pub prompt_tokens: Option<u64>,
+    #[serde(default, alias = "output_tokens")]
+    pub completion_tokens: Option<u64>,
+    #[serde(default)]
+    pub total_tokens: Option<u64>,
+}
 
-    let status = response.status().as_u16();
-    let network_time = send_start.elapsed();
+/// JSON ボディから `usage` を取り出す。JSON でない・含まれない場合は None
+fn parse_token_usage(body: &str) -> Option<TokenUsage> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let usage = value.get("usage")?;
+    if !usage.is_object() {
+        return None;
+    }
+    serde_json::from_value(usage.clone()).ok()
+}
 
-    // レスポンスヘッダーを取得
+/// エラーボディから `error` を取り出す。JSON でない・2xx/3xx の場合は None
+///
+/// `code` が数値で返るプロバイダーもあるため、文字列以外は文字列化して受け付ける。
+fn parse_error_details(status: u16, body: &str) -> Option<ApiErrorDetails> {
+    if status < 400 {
+        return None;
+    }
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let error = value.get("error")?;
+    let field = |name: &str| match error.get(name)? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    };
+
+    match error {
+        serde_json::Value::Object(_) => Some(ApiErrorDetails {
+            error_type: field("type"),
+            code: field("code"),
+            message: field("message"),
+            param: field("param"),
+        }),
+        // `{"error": "message"}` 形式のプロキシ等にも対応する
+        serde_json::Value::String(message) => Some(ApiErrorDetails {
+            message: Some(message.clone()),
+            ..ApiErrorDetails::default()
+        }),
+        _ => None,
+    }
+}
+
+fn collect_response_headers(
+    response_headers: &reqwest::header::HeaderMap,
+    allowlist: Option<&[String]>,
+) -> HashMap<String, String> {
     let mut headers = HashMap::new();
-    for (key, value) in response.headers() {
+    for (key, value) in response_headers {
+        if let Some(allowed) = allowlist {
+            if !allowed.iter().any(|name| name.trim().eq_ignore_ascii_case(key.as_str())) {
+                continue;
+            }
+        }
         if let Ok(value_str) = value.to_str() {
             headers.insert(key.to_string(), value_str.to_string());
         }
     }
+    headers
+}
 
-    // レスポンスボディを取得
-    let body = response
-        .text()
-        .await
-        .map_err(|e| format!("[Request {}] Failed to read response: {}", request_id, e))?;
+/// ログ出力用に API キーをマスクする（先頭と末尾 4 文字のみ残す）
+fn mask_api_key(api_key: &str) -> String {
+    let char_count = api_key.chars().count();
+    if char_count > 8 {
+        let head: String = api_key.chars().take(4).collect();
+        let tail: String = api_key.chars().skip(char_count - 4).collect();
+        format!("{}...{}", head, tail)
+    } else {
+        "****".to_string()
+    }
+}
 
-    let total_time = start_time.elapsed();
+/// ログ出力用にプロキシ URL から認証情報を取り除く
+fn redact_proxy_url(raw: &str) -> String {
+    match Url::parse(raw) {
+        Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+            url.to_string()
+        }
+        _ => raw.to_string(),
+    }
+}
 
-    log::info!(
-        "[Request {}] Upload complete | Status: {} | Network: {:?} | Total: {:?}",
-        request_id, status, network_time, total_time
-    );
+fn read_proxy_env(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+}
 
-    if status >= 400 {
-        log::error!("[Request {}] Upload error ({}): {}", request_id, status, body);
+/// 環境変数（HTTP_PROXY / HTTPS_PROXY / NO_PROXY）のプロキシ設定の概要を返す
+fn env_proxy_summary() -> Option<String> {
+    let http_proxy = read_proxy_env(&["HTTP_PROXY", "http_proxy"]);
+    let https_proxy = read_proxy_env(&["HTTPS_PROXY", "https_proxy"]);
+    let all_proxy = read_proxy_env(&["ALL_PROXY", "all_proxy"]);
+    if http_proxy.is_none() && https_proxy.is_none() && all_proxy.is_none() {
+        return None;
     }
 
-    Ok(OpenAIResponse {
-        status,
-        body,
-        headers,
+    let no_proxy = read_proxy_env(&["NO_PROXY", "no_proxy"]);
+    let mut parts = Vec::new();
+    if let Some(proxy) = http_proxy {
+        parts.push(format!("HTTP Proxy: {}", redact_proxy_url(&proxy)));
+    }
+    if let Some(proxy) = https_proxy {
+        parts.push(format!("HTTPS Proxy: {}", redact_proxy_url(&proxy)));
+    }
+    if let Some(proxy) = all_proxy {
+        parts.push(format!("ALL Proxy: {}", redact_proxy_url(&proxy)));
+    }
+    if let Some(no_proxy) = no_proxy {
+        parts.push(format!("NO_PROXY: {}", no_proxy));
+    }
+    Some(parts.join(", "))
+}
+
+/// ログ・診断用のプロキシ設定の概要（直接接続の場合は空文字）
+pub fn proxy_summary(proxy_config: &Option<ProxyConfig>) -> String {
+    let proxy_config = match proxy_config {
+        Some(proxy_config) => proxy_config,
+        None => {
+            return env_proxy_summary()
+                .map(|summary| format!("Environment ({})", summary))
+                .unwrap_or_default();
+        }
+    };
+
+    let mut parts = Vec::new();
+    if let Some(http_proxy) = proxy_config.http_proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        parts.push(format!("HTTP Proxy: {}", redact_proxy_url(http_proxy)));
+    }
+    if let Some(https_proxy) = proxy_config.https_proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        parts.push(format!("HTTPS Proxy: {}", redact_proxy_url(https_proxy)));
+    }
+    parts.join(", ")
+}
+
+/// プロキシ設定をクライアントビルダーに適用する
+///
+/// - `None`: 環境変数（HTTP_PROXY / HTTPS_PROXY、NO_PROXY を考慮）のプロキシを使用する
+///   （既定のプロキシ設定がある場合は ClientConfig::new の時点でそちらに置き換わる）
+/// - 空の `ProxyConfig`: 環境変数も無視して直接接続する
+/// - 値のある `ProxyConfig`: 指定したプロキシのみを使用する
+fn apply_proxy_config(
+    mut client_builder: reqwest::ClientBuilder,
+    proxy_config: &Option<ProxyConfig>,
+    request_id: &Uuid,
+) -> Result<reqwest::ClientBuilder, String> {
+    let proxy_info = proxy_summary(proxy_config);
+    let proxy_config = match proxy_config {
+        Some(proxy_config) => proxy_config,
+        None => {
+            // reqwest は既定で環境変数のプロキシ設定（NO_PROXY を含む）を使用する
+            if proxy_info.is_empty() {
+                log::info!("[Request {}] No proxy configuration, connecting directly", request_id);
+            } else {
+                log::info!("[Request {}] Using proxy settings from environment: {}", request_id, proxy_info);
+            }
+            return Ok(client_builder);
+        }
+    };
+
+    if let Some(http_proxy) = proxy_config.http_proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        let proxy = Proxy::http(http_proxy).map_err(|e| {
+            let err_msg = format!(
+                "[Request {}] HTTP proxy configuration error: {} (Proxy: {})",
+                request_id, e, redact_proxy_url(http_proxy)
+            );
+            log::error!("{}", err_msg);
+            err_msg
+        })?;
+        client_builder = client_builder.proxy(proxy);
+    }
+    if let Some(https_proxy) = proxy_config.https_proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+        let proxy = Proxy::https(https_proxy).map_err(|e| {
+            let err_msg = format!(
+                "[Request {}] HTTPS proxy configuration error: {} (Proxy: {})",
+                request_id, e, redact_proxy_url(https_proxy)
+            );
+            log::error!("{}", err_msg);
+            err_msg
+        })?;
+        client_builder = client_builder.proxy(proxy);
+    }
+
+    if !proxy_info.is_empty() {
+        log::info!("[Request {}] Proxy configuration applied: {}", request_id, proxy_info);
+    } else {
+        // 明示的に空の設定が渡された場合は環境変数のプロキシも使わない
+        client_builder = client_builder.no_proxy();
+        log::info!("[Request {}] Proxy explicitly disabled, connecting directly", request_id);
+    }
+
+    Ok(client_builder)
+}
+
+/// HTTP クライアントの設定（プロキシ・タイムアウト・TLS・User-Agent）
+///
+/// 同じ設定からは同じクライアントが作られるため、クライアントを使い回す際のキーにもなる。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ClientConfig {
+    pub proxy_config: Option<ProxyConfig>,
+    pub timeout_ms: Option<u64>,
+    pub connect_timeout_ms: Option<u64>,
+    pub min_tls_version: Option<String>, // "1.0" / "1.1" / "1.2" / "1.3"
+    pub user_agent: Option<String>,
+    pub use_system_certs: bool, // OS の証明書ストアのルート証明書も信頼する
+    pub pool_idle_timeout_ms: Option<u64>, // 未使用の接続を保持する時間（未指定時は reqwest の既定値 90 秒）
+    pub pool_max_idle_per_host: Option<usize>, // ホストごとに保持する未使用の接続数（未指定時は無制限）
+    pub http2_prior_knowledge: bool, // ALPN でネゴシエーションせず HTTP/2 で接続する
+}
+
+/// 接続プールの設定（set_connection_pool_config で実行時に変更する）
+///
+/// 保持する「未使用の」接続数を制限するだけで、同時に使う接続数は制限しない。
+/// このアプリには同時実行数の制限（セマフォ）が無いため、同時に送ったリクエストの数だけ接続が開き、
+/// 上限を超えた分は応答後に閉じられる。値を変えると別のクライアント（別の接続プール）になる。
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionPoolConfig {
+    #[serde(default)]
+    pub pool_idle_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+}
+
+static CONNECTION_POOL_CONFIG: Lazy<RwLock<ConnectionPoolConfig>> =
+    Lazy::new(|| RwLock::new(ConnectionPoolConfig::default()));
+
+pub fn set_connection_pool_config(config: ConnectionPoolConfig) -> Result<(), String> {
+    let mut current = CONNECTION_POOL_CONFIG
+        .write()
+        .map_err(|_| "接続プールの設定の更新に失敗しました".to_string())?;
+    *current = config;
+    Ok(())
+}
+
+/// 設定画面で保存した既定のプロキシ設定（リクエストに proxy_config がない場合に使う）
+static DEFAULT_PROXY_CONFIG: Lazy<RwLock<Option<ProxyConfig>>> = Lazy::new(|| RwLock::new(None));
+
+/// 既定のプロキシ設定を変更する（None で解除）
+pub fn set_default_proxy_config(proxy_config: Option<ProxyConfig>) -> Result<(), String> {
+    let mut current = DEFAULT_PROXY_CONFIG
+        .write()
+        .map_err(|_| "既定のプロキシ設定の更新に失敗しました".to_string())?;
+    *current = proxy_config;
+    Ok(())
+}
+
+/// リクエストの proxy_config がなければ既定の設定を使う
+///
+/// 空の ProxyConfig は「プロキシを使わない」指定のため、既定の設定で上書きしない。
+fn effective_proxy_config(proxy_config: &Option<ProxyConfig>) -> Option<ProxyConfig> {
+    match proxy_config {
+        Some(proxy_config) => Some(proxy_config.clone()),
+        None => DEFAULT_PROXY_CONFIG.read().ok().and_then(|current| current.clone()),
+    }
+}
+
+impl ClientConfig {
+    fn new(proxy_config: &Option<ProxyConfig>, timeout_ms: Option<u64>) -> Self {
+        let pool_config = CONNECTION_POOL_CONFIG.read().map(|config| *config).unwrap_or_default();
+        Self {
+            proxy_config: effective_proxy_config(proxy_config),
+            timeout_ms,
+            pool_idle_timeout_ms: pool_config.pool_idle_timeout_ms,
+            pool_max_idle_per_host: pool_config.pool_max_idle_per_host,
+            ..Self::default()
+        }
+    }
+}
+
+/// OS の証明書ストアから読み込んだルート証明書（初回利用時に 1 回だけ読み込む）
+static SYSTEM_CERTIFICATES: Lazy<Vec<reqwest::Certificate>> = Lazy::new(|| {
+    let result = rustls_native_certs::load_native_certs();
+    for err in &result.errors {
+        log::warn!("Failed to load a certificate from the system store: {}", err);
+    }
+    let certificates: Vec<reqwest::Certificate> = result
+        .certs
+        .iter()
+        .filter_map(|der| reqwest::Certificate::from_der(der.as_ref()).ok())
+        .collect();
+    log::info!("Loaded {} root certificate(s) from the system store", certificates.len());
+    certificates
+});
+
+fn parse_tls_version(raw: &str) -> Result<reqwest::tls::Version, String> {
+    match raw.trim() {
+        "1.0" => Ok(reqwest::tls::Version::TLS_1_0),
+        "1.1" => Ok(reqwest::tls::Version::TLS_1_1),
+        "1.2" => Ok(reqwest::tls::Version::TLS_1_2),
+        "1.3" => Ok(reqwest::tls::Version::TLS_1_3),
+        other => Err(format!("サポートされていない TLS バージョンです: {}", other)),
+    }
+}
+
+/// OpenAIRequest の送信に使うクライアント設定
+///
+/// HTTP/2 を強制すると HTTP/1.1 しか話せない平文の接続先・プロキシでは通信できないため、HTTPS の URL に限る。
+fn request_client_config(request: &OpenAIRequest, url: &str, request_id: &Uuid) -> Result<ClientConfig, String> {
+    let http2_prior_knowledge = request.http2_prior_knowledge.unwrap_or(false);
+    if http2_prior_knowledge && !url.starts_with("https://") {
+        let err_msg = format!(
+            "[Request {}] http2_prior_knowledge は HTTPS の URL でのみ使用できます: {}",
+            request_id, url
+        );
+        log::error!("{}", err_msg);
+        return Err(err_msg);
+    }
+
+    Ok(ClientConfig {
+        use_system_certs: request.use_system_certs.unwrap_or(false),
+        http2_prior_knowledge,
+        min_tls_version: request.min_tls_version.clone(),
+        ..ClientConfig::new(&request.proxy_config, None)
+    })
+}
+
+/// 設定から HTTP クライアントを作成する（すべてのリクエスト経路で共通）
+pub fn build_client(config: &ClientConfig, request_id: &Uuid) -> Result<Client, String> {
+    let mut client_builder = apply_proxy_config(Client::builder(), &config.proxy_config, request_id)?;
+
+    if let Some(timeout_ms) = config.timeout_ms {
+        client_builder = client_builder.timeout(Duration::from_millis(timeout_ms));
+    }
+    if let Some(connect_timeout_ms) = config.connect_timeout_ms {
+        client_builder = client_builder.connect_timeout(Duration::from_millis(connect_timeout_ms));
+    }
+    if let Some(min_tls_version) = &config.min_tls_version {
+        client_builder = client_builder.min_tls_version(parse_tls_version(min_tls_version)?);
+    }
+    if let Some(pool_idle_timeout_ms) = config.pool_idle_timeout_ms {
+        client_builder = client_builder.pool_idle_timeout(Duration::from_millis(pool_idle_timeout_ms));
+    }
+    if let Some(pool_max_idle_per_host) = config.pool_max_idle_per_host {
+        client_builder = client_builder.pool_max_idle_per_host(pool_max_idle_per_host);
+    }
+    if config.http2_prior_knowledge {
+        client_builder = client_builder.http2_prior_knowledge();
+    }
+    let user_agent = config
+        .user_agent
+        .as_deref()
+        .map(str::trim)
+        .filter(|ua| !ua.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(build_info::default_user_agent);
+    client_builder = client_builder.user_agent(user_agent);
+    // 社内 CA が OS の証明書ストアにしか無い環境向けに、既定のルート証明書に加えて信頼する
+    if config.use_system_certs {
+        for certificate in SYSTEM_CERTIFICATES.iter() {
+            client_builder = client_builder.add_root_certificate(certificate.clone());
+        }
+    }
+
+    client_builder.build().map_err(|e| {
+        let err_msg = format!("[Request {}] Failed to build HTTP client: {}", request_id, e);
+        log::error!("{}", err_msg);
+        err_msg
     })
 }
+
+// 使い回すクライアントの上限（超えたら作り直す）
+const MAX_CACHED_CLIENTS: usize = 16;
+
+/// 設定ごとに使い回す HTTP クライアント（接続プールを共有し、TLS ハンドシェイクを省く）
+static CLIENT_CACHE: Lazy<Mutex<HashMap<ClientConfig, Client>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 同じ設定のクライアントがあれば使い回し、なければ作成して保持する
+fn cached_client(config: &ClientConfig, request_id: &Uuid) -> Result<Client, String> {
+    if let Some(client) = CLIENT_CACHE.lock().ok().and_then(|cache| cache.get(config).cloned()) {
+        return Ok(client);
+    }
+
+    let client = build_client(config, request_id)?;
+    if let Ok(mut cache) = CLIENT_CACHE.lock() {
+        if cache.len() >= MAX_CACHED_CLIENTS {
+            cache.clear();
+        }
+        cache.insert(config.clone(), client.clone());
+    }
+    Ok(client)
+}
+
+/// 使い回しているクライアントを破棄し、破棄した件数を返す（次のリクエストで作り直す）
+pub fn clear_client_cache() -> usize {
+    match CLIENT_CACHE.lock() {
+        Ok(mut cache) => {
+            let cleared = cache.len();
+            cache.clear();
+            cleared
+        }
+        Err(_) => 0,
+    }
+}
+
+/// エラーの原因チェーンから OS レベルの I/O エラー種別を取り出す
+fn io_error_kind(error: &reqwest::Error) -> Option<std::io::ErrorKind> {
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            return Some(io_err.kind());
+        }
+        source = err.source();
+    }
+    None
+}
+
+// ネットワーク未接続を示す OS のエラーコード（ENETDOWN / ENETUNREACH / EHOSTUNREACH）
+#[cfg(windows)]
+const OFFLINE_OS_ERRORS: &[i32] = &[10050, 10051, 10065];
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const OFFLINE_OS_ERRORS: &[i32] = &[50, 51, 65];
+#[cfg(not(any(windows, target_os = "macos", target_os = "ios")))]
+const OFFLINE_OS_ERRORS: &[i32] = &[100, 101, 113];
+
+// 経路の有無だけを確認する宛先（UDP の connect はパケットを送信しない）
+const CONNECTIVITY_PROBE_ADDRS: &[&str] = &["8.8.8.8:53", "[2001:4860:4860::8888]:53"];
+
+/// ネットワークインターフェースが無い・経路が無いことによるエラーか
+fn is_offline_error(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            if io_err
+                .raw_os_error()
+                .is_some_and(|code| OFFLINE_OS_ERRORS.contains(&code))
+            {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
+}
+
+// 診断用の名前解決を待つ上限
+const DIAGNOSTIC_RESOLVE_TIMEOUT_MS: u64 = 3_000;
+
+/// 接続先のホスト名を解決し、結果を診断用の文字列で返す（プロキシ経由でも端末側で解決する）
+async fn describe_host_resolution(url: &str) -> String {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return format!("Host could not be determined from URL: {}", url),
+    };
+    let host = match parsed.host_str() {
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']').to_string(),
+        None => return format!("Host could not be determined from URL: {}", url),
+    };
+    let port = parsed.port_or_known_default().unwrap_or(443);
+
+    let lookup = tokio::net::lookup_host((host.clone(), port));
+    match tokio::time::timeout(Duration::from_millis(DIAGNOSTIC_RESOLVE_TIMEOUT_MS), lookup).await {
+        Ok(Ok(addrs)) => {
+            let addrs: Vec<String> = addrs.map(|addr| addr.ip().to_string()).collect();
+            if addrs.is_empty() {
+                format!("Host {} resolved to no addresses", host)
+            } else {
+                format!("Host {} resolved to {}", host, addrs.join(", "))
+            }
+        }
+        Ok(Err(err)) => format!("Host {} did not resolve: {}", host, err),
+        Err(_) => format!("Host {} did not resolve within {}ms", host, DIAGNOSTIC_RESOLVE_TIMEOUT_MS),
+    }
+}
+
+/// 外部への経路が存在するか（IPv4 / IPv6 のいずれかで経路があれば true）
+async fn has_network_route() -> bool {
+    for addr in CONNECTIVITY_PROBE_ADDRS {
+        let bind_addr = if addr.starts_with('[') { "[::]:0" } else { "0.0.0.0:0" };
+        if let Ok(socket) = tokio::net::UdpSocket::bind(bind_addr).await {
+            if socket.connect(addr).await.is_ok() {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn offline_error(request_id: &Uuid, e: &reqwest::Error) -> String {
+    format!(
+        "[Request {}] No internet connection detected: {} (Check that the network cable or Wi-Fi is connected)",
+        request_id, e
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectivityStatus {
+    pub online: bool,
+    pub checked_at_ms: u64,
+}
+
+/// オンライン状態を確認する（UI のオンライン/オフライン表示用。通信は発生しない）
+pub async fn check_connectivity() -> ConnectivityStatus {
+    let online = has_network_route().await;
+    let checked_at_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    log::debug!("Connectivity check: {}", if online { "online" } else { "offline" });
+    ConnectivityStatus { online, checked_at_ms }
+}
+
+/// 再送してもサーバー側で二重に処理されないリクエストか（冪等なメソッドか、冪等キー付き）
+fn is_transport_retry_safe(request: &OpenAIRequest) -> bool {
+    let idempotent_method = matches!(request.method.trim().to_ascii_uppercase().as_str(), "GET" | "HEAD" | "DELETE");
+    let has_idempotency_key = request
+        .idempotency_key
+        .as_deref()
+        .is_some_and(|idempotency_key| !idempotency_key.trim().is_empty());
+    idempotent_method || has_idempotency_key
+}
+
+/// 再送で回復し得る転送エラーか（接続リセット・broken pipe など）
+///
+/// DNS 解決の失敗は再送しても回復しないため対象外とする。
+fn is_retryable_transport_error(error: &reqwest::Error) -> bool {
+    let message = error.to_string().to_ascii_lowercase();
+    if message.contains("dns") || message.contains("resolve") {
+        return false;
+    }
+
+    matches!(
+        io_error_kind(error),
+        Some(std::io::ErrorKind::ConnectionReset)
+            | Some(std::io::ErrorKind::BrokenPipe)
+            | Some(std::io::ErrorKind::ConnectionAborted)
+    )
+}
+
+fn parse_header_value(value: &str, request_id: &Uuid) -> Result<HeaderValue, String> {
+    HeaderValue::from_str(value)
+        .map_err(|_| format!("[Request {}] Invalid header value: {}", request_id, value))
+}
+
+fn bearer_header_value(api_key: &str, request_id: &Uuid) -> Result<HeaderValue, String> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", api_key))
+        .map_err(|_| format!("[Request {}] API key contains characters not allowed in a header", request_id))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// API キーを送るヘッダー（auth_header 未指定時は Authorization: Bearer）
+fn api_key_header(auth_header: Option<&str>, api_key: &str, request_id: &Uuid) -> Result<(HeaderName, HeaderValue), String> {
+    let name = match auth_header.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("[Request {}] Invalid auth header name: {}", request_id, name))?,
+        None => AUTHORIZATION,
+    };
+    if name == AUTHORIZATION {
+        return Ok((name, bearer_header_value(api_key, request_id)?));
+    }
+
+    // Azure OpenAI の api-key などは Bearer を付けずにキーをそのまま送る
+    let mut value = HeaderValue::from_str(api_key)
+        .map_err(|_| format!("[Request {}] API key contains characters not allowed in a header", request_id))?;
+    value.set_sensitive(true);
+    Ok((name, value))
+}
+
+/// 組み込みヘッダーに追加ヘッダーを上書きで適用する
+///
+/// ヘッダー名は大文字小文字を区別せずに照合するため、`authorization` などを指定しても重複しない。
+fn merge_additional_headers(
+    mut headers: HeaderMap,
+    additional_headers: &Option<HashMap<String, String>>,
+    request_id: &Uuid,
+) -> HeaderMap {
+    for (key, value) in additional_headers.iter().flatten() {
+        if is_forbidden_header(key) {
+            log::warn!("[Request {}] Forbidden header dropped: {}", request_id, log_preview(key));
+            continue;
+        }
+        let (name, mut value) = match (HeaderName::from_bytes(key.trim().as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(name), Ok(value)) => (name, value),
+            _ => {
+                log::warn!("[Request {}] Invalid header dropped: {}", request_id, log_preview(key));
+                continue;
+            }
+        };
+        if headers.contains_key(&name) {
+            log::warn!("[Request {}] Additional header overrides built-in header: {}", request_id, name);
+        }
+        if name == AUTHORIZATION {
+            value.set_sensitive(true);
+        }
+        headers.insert(name, value);
+    }
+    headers
+}
+
+/// 送信エラーの分類（フロントエンドで原因別の案内を出すのに使う）
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SendErrorKind {
+    Offline,
+    Dns,
+    Tls,
+    ProxyAuth,
+    Connect,
+    Timeout,
+    Request,
+    Decode,
+    Other,
+}
+
+fn classify_send_error(e: &reqwest::Error) -> SendErrorKind {
+    if e.is_connect() {
+        let message = e.to_string();
+        if is_offline_error(e) {
+            SendErrorKind::Offline
+        } else if message.contains("dns") || message.contains("resolve") {
+            SendErrorKind::Dns
+        } else if message.contains("certificate") || message.contains("ssl") || message.contains("tls") {
+            SendErrorKind::Tls
+        } else if message.contains("407") || message.contains("Proxy Authentication") {
+            SendErrorKind::ProxyAuth
+        } else {
+            SendErrorKind::Connect
+        }
+    } else if e.is_timeout() {
+        SendErrorKind::Timeout
+    } else if e.is_request() {
+        SendErrorKind::Request
+    } else if e.is_decode() {
+        SendErrorKind::Decode
+    } else {
+        SendErrorKind::Other
+    }
+}
+
+/// 送信エラーを種別ごとに分類し、利用者向けのメッセージを作成する
+fn describe_send_error(request_id: &Uuid, e: &reqwest::Error, elapsed: Duration) -> String {
+    match classify_send_error(e) {
+        SendErrorKind::Offline => offline_error(request_id, e),
+        SendErrorKind::Dns => {
+            format!("[Request {}] DNS resolution failed: {} (Check domain name or DNS settings)", request_id, e)
+        }
+        SendErrorKind::Tls => {
+            format!("[Request {}] SSL/TLS error: {} (Check certificate validity or security settings)", request_id, e)
+        }
+        SendErrorKind::ProxyAuth => {
+            format!("[Request {}] Proxy authentication required: {} (Check proxy credentials)", request_id, e)
+        }
+        SendErrorKind::Connect => {
+            format!("[Request {}] Connection failed: {} (Check network/proxy settings)", request_id, e)
+        }
+        SendErrorKind::Timeout => format!("[Request {}] Request timeout after {:?}: {}", request_id, elapsed, e),
+        SendErrorKind::Request => format!("[Request {}] Request error: {}", request_id, e),
+        SendErrorKind::Decode => format!("[Request {}] Response decode error: {}", request_id, e),
+        SendErrorKind::Other => format!("[Request {}] Failed to send request: {}", request_id, e),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProxyTestResult {
+    pub status: u16,
+    pub latency_ms: u64,
+    pub proxy_info: String,
+}
+
+/// プロキシ設定だけで疎通確認を行う（API キーは送信しない）
+///
+/// 応答があればステータスコードに関わらず成功とみなす（401 でもプロキシ経由で到達できている）。
+/// 407 はプロキシ認証の失敗として扱う。
+pub async fn test_proxy_connection(
+    proxy_config: Option<ProxyConfig>,
+    test_url: Option<String>,
+) -> Result<ProxyTestResult, String> {
+    let request_id = Uuid::new_v4();
+    let test_url = test_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_PROXY_TEST_URL.to_string());
+
+    let url = Url::parse(&test_url).map_err(|_| format!("テスト用 URL が正しくありません: {}", test_url))?;
+    validate_target_url(&url, false)?;
+
+    let client_config = ClientConfig::new(&proxy_config, Some(PROXY_TEST_TIMEOUT_MS));
+    let proxy_info = proxy_summary(&client_config.proxy_config);
+    let client = build_client(&client_config, &request_id)?;
+
+    log::info!("[Request {}] Testing proxy connection to {} | Proxy: {}", request_id, url, proxy_info);
+    let start_time = Instant::now();
+    let response = client.head(url).send().await.map_err(|e| {
+        let err_msg = describe_send_error(&request_id, &e, start_time.elapsed());
+        log::warn!("[Request {}] Proxy connection test failed: {}", request_id, err_msg);
+        err_msg
+    })?;
+
+    let status = response.status().as_u16();
+    let latency_ms = start_time.elapsed().as_millis() as u64;
+    if status == 407 {
+        let err_msg = format!(
+            "[Request {}] Proxy authentication required: 407 (Check proxy credentials)",
+            request_id
+        );
+        log::warn!("{}", err_msg);
+        return Err(err_msg);
+    }
+
+    log::info!(
+        "[Request {}] Proxy connection test succeeded | Status: {} | Latency: {}ms",
+        request_id, status, latency_ms
+    );
+    Ok(ProxyTestResult {
+        status,
+        latency_ms,
+        proxy_info,
+    })
+}
+
+const WARMUP_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Debug, Serialize)]
+pub struct WarmupResult {
+    pub status: u16,
+    pub latency_ms: u64,
+    pub proxy_info: String,
+}
+
+/// 通常のリクエストと同じクライアントで接続を確立し、接続プールに残しておく
+///
+/// アイドル後の最初のリクエストで TLS ハンドシェイクを待たないようにする。
+/// API キーは送らず HEAD を送るだけなので、タイマーで定期的に呼び出してもよい。
+pub async fn warmup_connection(
+    base_url: String,
+    proxy_config: Option<ProxyConfig>,
+) -> Result<WarmupResult, String> {
+    let request_id = Uuid::new_v4();
+    let url = build_request_url(&base_url, "models", false).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    // proxy_openai_request / proxy_openai_stream と同じ設定にして、同じ接続プールを温める
+    let client_config = ClientConfig::new(&proxy_config, None);
+    let proxy_info = proxy_summary(&client_config.proxy_config);
+    let client = cached_client(&client_config, &request_id)?;
+
+    let start_time = Instant::now();
+    let response = client
+        .head(&url)
+        .timeout(Duration::from_millis(WARMUP_TIMEOUT_MS))
+        .send()
+        .await
+        .map_err(|e| {
+            let err_msg = describe_send_error(&request_id, &e, start_time.elapsed());
+            log::warn!("[Request {}] Connection warm-up failed: {}", request_id, err_msg);
+            err_msg
+        })?;
+
+    // 応答があれば接続は確立できている（API キーなしのため通常は 401）
+    let status = response.status().as_u16();
+    let latency_ms = start_time.elapsed().as_millis() as u64;
+    log::info!(
+        "[Request {}] Connection warmed up for {} | Status: {} | Latency: {}ms | Proxy: {}",
+        request_id, url, status, latency_ms, if proxy_info.is_empty() { "none" } else { &proxy_info }
+    );
+    Ok(WarmupResult {
+        status,
+        latency_ms,
+        proxy_info,
+    })
+}
+
+const VALIDATE_API_KEY_TIMEOUT_MS: u64 = 10_000;
+
+/// API キーの検証結果
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ApiKeyValidation {
+    Valid { latency_ms: u64 },
+    /// 401/403
+    Invalid { auth_error: AuthError, message: Option<String> },
+    /// 429（キー自体は有効）
+    RateLimited { retry_after: Option<String>, message: Option<String> },
+    NetworkError { kind: SendErrorKind, message: String },
+    UnexpectedStatus { status: u16, message: Option<String> },
+}
+
+/// /models への GET で API キーが有効か確認する（トークンは消費しない）
+pub async fn validate_api_key(
+    base_url: String,
+    api_key: String,
+    proxy_config: Option<ProxyConfig>,
+) -> Result<ApiKeyValidation, String> {
+    let request_id = Uuid::new_v4();
+    let url = build_request_url(&base_url, "models", false).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client_config = ClientConfig::new(&proxy_config, Some(VALIDATE_API_KEY_TIMEOUT_MS));
+    let proxy_info = proxy_summary(&client_config.proxy_config);
+    let client = build_client(&client_config, &request_id)?;
+
+    log::info!(
+        "[Request {}] Validating API key {} against {} | Proxy: {}",
+        request_id, mask_api_key(&api_key), url, proxy_info
+    );
+    let start_time = Instant::now();
+    let send_result = client
+        .get(&url)
+        .header(AUTHORIZATION, bearer_header_value(&api_key, &request_id)?)
+        .send()
+        .await;
+    let response = match send_result {
+        Ok(response) => response,
+        Err(e) => {
+            let message = describe_send_error(&request_id, &e, start_time.elapsed());
+            log::warn!("[Request {}] API key validation failed: {}", request_id, message);
+            return Ok(ApiKeyValidation::NetworkError {
+                kind: classify_send_error(&e),
+                message,
+            });
+        }
+    };
+
+    let status = response.status().as_u16();
+    let latency_ms = start_time.elapsed().as_millis() as u64;
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    // 成功時はモデル一覧を読まずに破棄する
+    let message = if (200..300).contains(&status) {
+        None
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        parse_error_details(status, &body).and_then(|details| details.message)
+    };
+
+    let result = match (status, AuthError::from_status(status)) {
+        (_, Some(auth_error)) => ApiKeyValidation::Invalid { auth_error, message },
+        (200..=299, None) => ApiKeyValidation::Valid { latency_ms },
+        (407, None) => ApiKeyValidation::NetworkError {
+            kind: SendErrorKind::ProxyAuth,
+            message: format!("[Request {}] Proxy authentication required: 407 (Check proxy credentials)", request_id),
+        },
+        (429, None) => ApiKeyValidation::RateLimited { retry_after, message },
+        (status, None) => ApiKeyValidation::UnexpectedStatus { status, message },
+    };
+    log::info!(
+        "[Request {}] API key validation finished | Status: {} | Latency: {}ms | Result: {:?}",
+        request_id, status, latency_ms, result
+    );
+    Ok(result)
+}
+
+/// 資格情報ストアの参照があれば送信直前に API キーを解決する
+async fn resolve_request_api_key(request: &OpenAIRequest, request_id: &Uuid) -> Result<String, String> {
+    match request.api_key_ref.as_deref().map(str::trim) {
+        Some(profile_name) if !profile_name.is_empty() => {
+            keychain::resolve_api_key_ref(profile_name).await.map_err(|err| {
+                log::error!("[Request {}] API key lookup failed: {}", request_id, err);
+                err
+            })
+        }
+        _ => Ok(request.api_key.clone()),
+    }
+}
+
+// プレビューで値をマスクするヘッダー（小文字）
+const SENSITIVE_PREVIEW_HEADERS: &[&str] = &["authorization", "proxy-authorization", "api-key", "x-api-key", "cookie"];
+
+/// 機密ヘッダーの値をマスクする（Bearer トークンはキー部分のみ残して先頭・末尾以外を隠す）
+fn mask_sensitive_header(name: &str, value: &str) -> String {
+    if SENSITIVE_PREVIEW_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+        let secret = value.strip_prefix("Bearer ").unwrap_or(value);
+        mask_api_key(secret)
+    } else {
+        value.to_string()
+    }
+}
+
+/// 送信せずに、組み立てたリクエストの内容（機密ヘッダーはマスク）を返す
+fn build_dry_run_preview(
+    req_builder: reqwest::RequestBuilder,
+    request_id: &Uuid,
+    body_size: usize,
+    proxy_info: &str,
+) -> Result<OpenAIResponse, String> {
+    let built = req_builder
+        .build()
+        .map_err(|e| format!("[Request {}] Failed to build request: {}", request_id, e))?;
+
+    let headers: serde_json::Map<String, serde_json::Value> = built
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let raw = value.to_str().unwrap_or("<binary>");
+            // auth_header で指定した独自のヘッダー名も API キーとしてマスクする
+            let masked = if value.is_sensitive() {
+                mask_api_key(raw.strip_prefix("Bearer ").unwrap_or(raw))
+            } else {
+                mask_sensitive_header(name.as_str(), raw)
+            };
+            (name.to_string(), serde_json::Value::String(masked))
+        })
+        .collect();
+
+    let preview = serde_json::json!({
+        "dry_run": true,
+        "method": built.method().as_str(),
+        "url": built.url().as_str(),
+        "headers": headers,
+        "body_size": body_size,
+        "proxy": if proxy_info.is_empty() { "Direct connection" } else { proxy_info },
+    });
+    log::info!("[Request {}] Dry run: request not sent", request_id);
+
+    Ok(OpenAIResponse {
+        status: 0,
+        body: preview.to_string(),
+        headers: HashMap::new(),
+        usage: None,
+        error_details: None,
+        auth_error: None,
+        dry_run: true,
+        served_by: None,
+        timing: None,
+        remote_addr: None,
+        attempts: 0,
+    })
+}
+
+fn method_request_builder(client: &Client, method: &str, url: &str) -> Result<reqwest::RequestBuilder, String> {
+    match method.to_uppercase().as_str() {
+        "GET" => Ok(client.get(url)),
+        "POST" => Ok(client.post(url)),
+        "PUT" => Ok(client.put(url)),
+        "DELETE" => Ok(client.delete(url)),
+        "PATCH" => Ok(client.patch(url)),
+        _ => Err(format!("Unsupported HTTP method: {}", method)),
+    }
+}
+
+/// ボディと Content-Type・冪等キーを設定する（raw_body があれば JSON 変換せずそのまま送る）
+fn apply_request_body(
+    mut req_builder: reqwest::RequestBuilder,
+    request: &OpenAIRequest,
+    builtin_headers: &mut HeaderMap,
+    request_id: &Uuid,
+) -> Result<reqwest::RequestBuilder, String> {
+    let content_type = request
+        .content_type
+        .as_deref()
+        .map(str::trim)
+        .filter(|content_type| !content_type.is_empty());
+    if let Some(raw_body) = &request.raw_body {
+        builtin_headers.insert(
+            CONTENT_TYPE,
+            parse_header_value(content_type.unwrap_or("text/plain; charset=utf-8"), request_id)?,
+        );
+        req_builder = req_builder.body(raw_body.clone());
+    } else if let Some(body) = &request.body {
+        builtin_headers.insert(
+            CONTENT_TYPE,
+            parse_header_value(content_type.unwrap_or("application/json"), request_id)?,
+        );
+        req_builder = req_builder.json(body);
+    }
+
+    // 冪等キーはリクエスト単位で固定し、同じリクエストの再送でも同じキーを使う
+    if let Some(idempotency_key) = request.idempotency_key.as_deref().map(str::trim) {
+        if !idempotency_key.is_empty() {
+            log::info!("[Request {}] Idempotency-Key: {}", request_id, log_preview(idempotency_key));
+            builtin_headers.insert("idempotency-key", parse_header_value(idempotency_key, request_id)?);
+        }
+    }
+    Ok(req_builder)
+}
+
+/// 期限までに future が完了しなければ None を返す（期限なしの場合はそのまま待つ）
+async fn with_deadline<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(tokio::time::Instant::from_std(deadline), future)
+            .await
+            .ok(),
+        None => Some(future.await),
+    }
+}
+
+fn deadline_exceeded_error(request_id: &Uuid, deadline_ms: u64) -> String {
+    let err_msg = format!(
+        "[Request {}] Request failed: overall deadline exceeded ({}ms)",
+        request_id, deadline_ms
+    );
+    log::error!("{}", err_msg);
+    err_msg
+}
+
+/// 送信の失敗（`transient` は接続・タイムアウト・遮断中など、別の宛先や時間をおけば成功し得るエラー）
+struct AttemptError {
+    message: String,
+    transient: bool,
+}
+
+impl AttemptError {
+    fn transient(message: String) -> Self {
+        Self { message, transient: true }
+    }
+}
+
+impl From<String> for AttemptError {
+    fn from(message: String) -> Self {
+        Self { message, transient: false }
+    }
+}
+
+// 進捗通知の要らないテスト用の短縮形
+#[cfg(test)]
+async fn make_openai_request(request: OpenAIRequest) -> Result<OpenAIResponse, String> {
+    make_openai_request_with_progress(request, |_| {}).await
+}
+
+/// `progress_id` を指定したリクエストでは、ボディの受信状況を `on_progress` で通知する
+pub async fn make_openai_request_with_progress<F>(request: OpenAIRequest, on_progress: F) -> Result<OpenAIResponse, String>
+where
+    F: Fn(ResponseProgress) + Send + Sync,
+{
+    tracked_openai_request(request, &on_progress).await.map_err(|err| err.message)
+}
+
+/// メトリクス・直近のリクエストの記録を行いながら送信する（エラーの種別を呼び出し元に残す）
+async fn tracked_openai_request<F>(request: OpenAIRequest, on_progress: &F) -> Result<OpenAIResponse, AttemptError>
+where
+    F: Fn(ResponseProgress) + Send + Sync,
+{
+    // リクエストIDを生成
+    let request_id = Uuid::new_v4();
+    let start_time = Instant::now();
+    let request = apply_provider_profile(request, &request_id)?;
+    enforce_request_features(&request, &secure_config::active_feature_restrictions(), &request_id)?;
+    let bytes_sent = request.body_len() as u64;
+    let dry_run = request.dry_run.unwrap_or(false);
+
+    // 直近のリクエストの記録が有効なら、送信前の内容を控えておく
+    let trace_config = request_trace::current_config();
+    let trace = (trace_config.enabled && !dry_run)
+        .then(|| start_trace_entry(&request, &request_id, trace_config.include_bodies));
+
+    let group_id = request.group_id.clone();
+    let mut transient = false;
+    let result = run_cancellable_in_group(request_id, group_id, async {
+        send_with_fallbacks(request, request_id, on_progress).await.map_err(|err| {
+            transient = err.transient;
+            err.message
+        })
+    })
+    .await;
+    if !dry_run {
+        metrics::record_request(&result, bytes_sent, start_time.elapsed());
+    }
+    if let Some(entry) = trace {
+        request_trace::record(finish_trace_entry(entry, &result, trace_config.include_bodies));
+    }
+    result.map_err(|message| AttemptError { message, transient })
+}
+
+fn start_trace_entry(request: &OpenAIRequest, request_id: &Uuid, include_bodies: bool) -> RecentRequest {
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let url = resolve_request_url(request, allow_insecure_http)
+        .unwrap_or_else(|_| format!("{}/{}", request.base_url.trim(), request.path.trim_start_matches('/')));
+
+    let mut headers: HashMap<String, String> = request
+        .additional_headers
+        .iter()
+        .flatten()
+        .map(|(name, value)| (name.to_ascii_lowercase(), mask_sensitive_header(name, value)))
+        .collect();
+    if !request.api_key.is_empty() {
+        let auth_header = request
+            .auth_header
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or("authorization");
+        headers
+            .entry(auth_header.to_ascii_lowercase())
+            .or_insert_with(|| mask_api_key(&request.api_key));
+    }
+
+    let request_body = if include_bodies {
+        match (&request.raw_body, &request.body) {
+            (Some(raw_body), _) => Some(log_preview(raw_body)),
+            (None, Some(body)) => Some(log_preview(&body.to_string())),
+            (None, None) => None,
+        }
+    } else {
+        None
+    };
+
+    RecentRequest {
+        request_id: request_id.to_string(),
+        timestamp_ms: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0),
+        method: request.method.to_uppercase(),
+        url,
+        headers,
+        body_size: request.body_len(),
+        status: None,
+        timing: None,
+        remote_addr: None,
+        attempts: 0,
+        error: None,
+        request_body,
+        response_body: None,
+    }
+}
+
+fn finish_trace_entry(
+    mut entry: RecentRequest,
+    result: &Result<OpenAIResponse, String>,
+    include_bodies: bool,
+) -> RecentRequest {
+    match result {
+        Ok(response) => {
+            entry.status = Some(response.status);
+            entry.timing = response.timing.clone();
+            entry.remote_addr = response.remote_addr.clone();
+            entry.attempts = response.attempts;
+            if include_bodies {
+                entry.response_body = Some(log_preview(&response.body));
+            }
+        }
+        Err(err) => entry.error = Some(err.clone()),
+    }
+    entry
+}
+
+/// プライマリのベースURLで失敗した場合に、フォールバック先へ順に同じリクエストを送る
+///
+/// 5xx と接続・タイムアウト・遮断中のエラーのみフォールバックする。4xx や設定の誤りは
+/// どのベースURLでも同じ結果になるためフォールバックしない。全体の期限はフォールバックも含めて数える。
+async fn send_with_fallbacks<F>(request: OpenAIRequest, request_id: Uuid, on_progress: &F) -> Result<OpenAIResponse, AttemptError>
+where
+    F: Fn(ResponseProgress) + Send + Sync,
+{
+    // raw_url はベースURLを使わないため、フォールバックもしない
+    let uses_raw_url = request.raw_url.as_deref().is_some_and(|raw_url| !raw_url.trim().is_empty());
+    let fallback_base_urls: Vec<String> = request
+        .fallback_base_urls
+        .iter()
+        .flatten()
+        .map(|base_url| base_url.trim().to_string())
+        .filter(|base_url| !base_url.is_empty() && !uses_raw_url)
+        .collect();
+    if uses_raw_url && request.fallback_base_urls.as_ref().is_some_and(|urls| !urls.is_empty()) {
+        log::warn!("[Request {}] raw_url is set, ignoring fallback_base_urls", request_id);
+    }
+    let base_urls: Vec<String> = std::iter::once(request.base_url.clone())
+        .chain(fallback_base_urls)
+        .collect();
+    let base_url_count = base_urls.len();
+    let start_time = Instant::now();
+    let deadline_ms = request.overall_deadline_ms.filter(|ms| *ms > 0);
+    let deadline = deadline_ms.map(|ms| start_time + Duration::from_millis(ms));
+    let mut attempts = 0;
+
+    let mut last_result = Err(format!("[Request {}] No base URL to try", request_id).into());
+    for (index, base_url) in base_urls.into_iter().enumerate() {
+        if index > 0 {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                log::warn!("[Request {}] Overall deadline exceeded, not falling back to {}", request_id, base_url);
+                break;
+            }
+            log::warn!(
+                "[Request {}] Falling back to base URL {}/{}: {}",
+                request_id, index + 1, base_url_count, base_url
+            );
+        }
+
+        let mut attempt = request.clone();
+        attempt.base_url = base_url.clone();
+        let result = send_openai_request(attempt, request_id, deadline, &mut attempts, on_progress).await;
+
+        let should_fall_back = match &result {
+            Ok(response) => {
+                log::info!("[Request {}] Base URL {} responded with {}", request_id, base_url, response.status);
+                response.status >= 500
+            }
+            Err(err) => {
+                log::warn!("[Request {}] Base URL {} failed: {}", request_id, base_url, err.message);
+                err.transient
+            }
+        };
+
+        last_result = result.map(|mut response| {
+            response.served_by = if uses_raw_url { None } else { Some(base_url) };
+            response.attempts = attempts;
+            if let Some(timing) = response.timing.as_mut() {
+                timing.total_ms = duration_ms(start_time.elapsed());
+            }
+            response
+        });
+        if !should_fall_back {
+            break;
+        }
+    }
+    last_result
+}
+
+/// `attempts` は実際に送信するたびに加算する（`deadline` とともにフォールバック全体で共有する）
+async fn send_openai_request<F>(
+    request: OpenAIRequest,
+    request_id: Uuid,
+    deadline: Option<Instant>,
+    attempts: &mut u32,
+    on_progress: &F,
+) -> Result<OpenAIResponse, AttemptError>
+where
+    F: Fn(ResponseProgress) + Send + Sync,
+{
+    let start_time = Instant::now();
+
+    log::info!("[Request {}] Starting new request", request_id);
+
+    // URLを構築（クライアント作成前に検証する）
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let url = resolve_request_url(&request, allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    // プロキシ設定を適用したクライアントを作成
+    let client_config = request_client_config(&request, &url, &request_id)?;
+    let proxy_info = proxy_summary(&client_config.proxy_config);
+    let client = cached_client(&client_config, &request_id)?;
+
+    let api_key = resolve_request_api_key(&request, &request_id).await?;
+
+    // APIキーをマスクしてログ出力
+    let masked_api_key = mask_api_key(&api_key);
+
+    // GET はキャッシュが有効ならネットワークに出ずに返す
+    let dry_run = request.dry_run.unwrap_or(false);
+    let cache_ttl = request
+        .cache_ttl_ms
+        .filter(|ttl| *ttl > 0 && !dry_run && request.method.eq_ignore_ascii_case("GET"))
+        .map(Duration::from_millis);
+    let cache_key = cache_ttl.map(|_| {
+        response_cache::cache_key(&request.method, &url, &request.additional_headers, &api_key)
+    });
+    let mut revalidation_etag = None;
+    if let Some(key) = cache_key.as_deref() {
+        match response_cache::lookup(key) {
+            response_cache::CacheLookup::Fresh(cached) => {
+                log::info!(
+                    "[Request {}] Cache hit: {} {} | Status: {} | Size: {} bytes",
+                    request_id, request.method, url, cached.status, cached.body.len()
+                );
+                let mut cached = *cached;
+                cached.timing = None;
+                cached.remote_addr = None;
+                cached.served_by = None;
+                cached.attempts = *attempts;
+                return Ok(cached);
+            }
+            response_cache::CacheLookup::Stale { etag } => {
+                log::info!("[Request {}] Cache expired, revalidating with ETag {}", request_id, log_preview(&etag));
+                revalidation_etag = Some(etag);
+            }
+            response_cache::CacheLookup::Miss => {}
+        }
+    }
+
+    // 追加ヘッダーの数を記録
+    let custom_headers_count = request.additional_headers.as_ref().map_or(0, |h| h.len());
+
+    // ボディサイズを計算
+    let body_size = request.body_len();
+
+    log::info!(
+        "[Request {}] {} {} | API Key: {} | Custom Headers: {} | Body Size: {} bytes",
+        request_id, request.method, url, masked_api_key, custom_headers_count, body_size
+    );
+
+    // リクエストビルダーを作成
+    let mut req_builder = method_request_builder(&client, &request.method, &url)?;
+
+    let mut builtin_headers = HeaderMap::new();
+    req_builder = apply_request_body(req_builder, &request, &mut builtin_headers, &request_id)?;
+
+    // キャッシュ済みの ETag があれば条件付きリクエストにする
+    if let Some(etag) = &revalidation_etag {
+        builtin_headers.insert(reqwest::header::IF_NONE_MATCH, parse_header_value(etag, &request_id)?);
+    }
+
+    let (auth_header_name, auth_header_value) = api_key_header(request.auth_header.as_deref(), &api_key, &request_id)?;
+    builtin_headers.insert(auth_header_name, auth_header_value);
+
+    // 追加ヘッダーは組み込みヘッダーの後に上書きで適用する（重複させない）
+    let headers = merge_additional_headers(builtin_headers, &request.additional_headers, &request_id);
+    req_builder = req_builder.headers(headers);
+
+    if dry_run {
+        return Ok(build_dry_run_preview(req_builder, &request_id, body_size, &proxy_info)?);
+    }
+
+    // 遮断中のベースURLには送信せずにエラーを返す（フォールバック先があればそちらを試す）
+    let breaker_key = circuit_key(&request, &url, allow_insecure_http);
+    circuit_breaker::check(&breaker_key).map_err(|err| {
+        let err_msg = format!("[Request {}] {}", request_id, err);
+        log::warn!("{}", err_msg);
+        AttemptError::transient(err_msg)
+    })?;
+
+    // リクエストを送信（接続リセット等の一時的な転送エラーは 1 回だけ再送する）
+    log::info!("[Request {}] Sending request...", request_id);
+    let send_start = Instant::now();
+    let setup_ms = duration_ms(send_start.duration_since(start_time));
+    let deadline_ms = request.overall_deadline_ms.unwrap_or_default();
+    let retry_safe = is_transport_retry_safe(&request);
+    let mut transport_retries = 0;
+    let mut next_builder = req_builder;
+    let response = loop {
+        // 再送用に複製を残しておく（冪等でないリクエストや複製できないボディの場合は再送しない）
+        let retry_builder = if retry_safe && transport_retries < MAX_TRANSPORT_RETRIES {
+            next_builder.try_clone()
+        } else {
+            None
+        };
+
+        *attempts += 1;
+        let send_result = match with_deadline(deadline, next_builder.send()).await {
+            Some(result) => result,
+            None => {
+                return Err(deadline_exceeded_error(&request_id, deadline_ms).into());
+            }
+        };
+
+        match send_result {
+            Ok(response) => break response,
+            Err(e) => {
+                let elapsed = send_start.elapsed();
+                let io_kind = io_error_kind(&e);
+                let deadline_passed = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+
+                if let Some(retry_builder) = retry_builder.filter(|_| is_retryable_transport_error(&e) && !deadline_passed) {
+                    transport_retries += 1;
+                    metrics::record_retry();
+                    log::warn!(
+                        "[Request {}] Transient transport error after {:?} (io error kind: {:?}), retrying ({}/{}): {}",
+                        request_id, elapsed, io_kind, transport_retries, MAX_TRANSPORT_RETRIES, e
+                    );
+                    next_builder = retry_builder;
+                    continue;
+                }
+
+                // 接続エラーは経路の有無を確認し、未接続なら DNS・プロキシの問題と区別して返す
+                let mut err_msg = if e.is_connect() && !is_offline_error(&e) && !has_network_route().await {
+                    offline_error(&request_id, &e)
+                } else {
+                    describe_send_error(&request_id, &e, elapsed)
+                };
+                if deadline_passed && is_retryable_transport_error(&e) {
+                    err_msg.push_str(" (overall deadline exceeded, not retried)");
+                }
+                // DNS・接続エラーは名前解決の結果を添えて、どのアドレスに接続しようとしたか分かるようにする
+                let error_kind = classify_send_error(&e);
+                if matches!(error_kind, SendErrorKind::Dns | SendErrorKind::Connect | SendErrorKind::Tls) {
+                    let resolution = describe_host_resolution(&url).await;
+                    if error_kind == SendErrorKind::Dns {
+                        err_msg.push_str(&format!(" | {}", resolution));
+                    } else {
+                        log::error!("[Request {}] {}", request_id, resolution);
+                    }
+                }
+                log::error!("{}", err_msg);
+                log::error!(
+                    "[Request {}] Request failed after {:?} (io error kind: {:?})",
+                    request_id, elapsed, io_kind
+                );
+
+                // プロキシが設定されている場合は追加情報を出力
+                if !proxy_info.is_empty() {
+                    log::error!("[Request {}] Active proxy configuration: {}", request_id, proxy_info);
+                }
+
+                circuit_breaker::record_failure(&breaker_key);
+                // 接続できない・応答がない場合のみ、別のベースURLで成功する見込みがある
+                let transient = matches!(
+                    error_kind,
+                    SendErrorKind::Dns | SendErrorKind::Connect | SendErrorKind::Tls | SendErrorKind::Timeout
+                );
+                return Err(AttemptError { message: err_msg, transient });
+            }
+        }
+    };
+
+    // ステータスコードを取得
+    let status = response.status().as_u16();
+    let network_time = send_start.elapsed();
+    let remote_addr = response.remote_addr().map(|addr| addr.to_string());
+    if status >= 500 {
+        circuit_breaker::record_failure(&breaker_key);
+    } else {
+        circuit_breaker::record_success(&breaker_key);
+    }
+
+    // 304 Not Modified はキャッシュ済みレスポンスをそのまま返す（ステータスは 200 のまま）
+    if status == 304 && revalidation_etag.is_some() {
+        if let Some(mut cached) = cache_key.as_deref().and_then(response_cache::revalidate) {
+            log::info!(
+                "[Request {}] Not modified, serving cached response | Size: {} bytes | Network: {:?}",
+                request_id, cached.body.len(), network_time
+            );
+            cached.timing = Some(RequestTiming {
+                setup_ms,
+                queue_wait_ms: None,
+                ttfb_ms: Some(duration_ms(network_time)),
+                download_ms: None,
+                total_ms: duration_ms(start_time.elapsed()),
+            });
+            cached.remote_addr = remote_addr;
+            cached.served_by = None;
+            cached.attempts = *attempts;
+            return Ok(cached);
+        }
+    }
+
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    // レスポンスヘッダーを取得（許可リストがあれば該当ヘッダーのみ）
+    let headers = collect_response_headers(response.headers(), request.response_header_allowlist.as_deref());
+
+    // レスポンスボディを取得（受信しながらサイズ制限を確認する）
+    let max_response_bytes = effective_max_response_bytes(request.max_response_bytes);
+    let download_start = Instant::now();
+    let progress_id = request
+        .progress_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|progress_id| !progress_id.is_empty());
+    let body_bytes = with_deadline(
+        deadline,
+        read_response_body(response, max_response_bytes, progress_id, on_progress, &request_id),
+    )
+    .await
+    .ok_or_else(|| deadline_exceeded_error(&request_id, deadline_ms))??;
+    // ログ出力・キャッシュより前に、管理者が指定した項目を取り除く
+    let body = redact_response_body(
+        decode_response_body(body_bytes, &request_id),
+        &secure_config::active_response_redaction(),
+        &request_id,
+    );
+
+    let response_size = body.len();
+    let download_time = download_start.elapsed();
+    let total_time = start_time.elapsed();
+
+    // ログ出力
+    log::info!(
+        "[Request {}] Response received | Status: {} | Size: {} bytes | Network: {:?} | Total: {:?}",
+        request_id, status, response_size, network_time, total_time
+    );
+
+    let log_limits = current_log_limits();
+    let log_response_body = request
+        .log_response_body
+        .unwrap_or(log_limits.log_response_bodies);
+
+    // レスポンスボディをログに出力（デバッグ用、set_log_level で debug 以上のときのみ）
+    if log_response_body && request.path.contains("/responses") && log::log_enabled!(log::Level::Debug) {
+        log::debug!("[Request {}] Response body: {}", request_id, log_preview(&body));
+    }
+
+    // 大きなレスポンスの警告
+    if response_size > large_response_warning_bytes(&log_limits, max_response_bytes) {
+        log::warn!("[Request {}] Large response detected: {} MB", request_id, response_size / 1024 / 1024);
+    }
+
+    let auth_error = AuthError::from_status(status);
+    match auth_error {
+        Some(AuthError::InvalidCredentials) => log::warn!(
+            "[Request {}] Authentication failed (401): API key rejected | API Key: {}",
+            request_id, masked_api_key
+        ),
+        Some(AuthError::Forbidden) => log::warn!(
+            "[Request {}] Authorization failed (403): API key lacks permission | API Key: {}",
+            request_id, masked_api_key
+        ),
+        None => {}
+    }
+
+    // エラーレスポンスの場合はログに出力
+    if status >= 400 && !log_response_body {
+        log::error!(
+            "[Request {}] OpenAI API error ({}): body omitted ({} bytes)",
+            request_id, status, response_size
+        );
+    } else if status >= 400 {
+        // エラーボディを省略表示（長すぎる場合）
+        log::error!("[Request {}] OpenAI API error ({}): {}", request_id, status, log_preview(&body));
+    } else {
+        log::info!("[Request {}] Request completed successfully", request_id);
+    }
+
+    let usage = parse_token_usage(&body);
+    let error_details = parse_error_details(status, &body);
+
+    let response = OpenAIResponse {
+        status,
+        error_details,
+        auth_error,
+        body,
+        headers,
+        usage,
+        dry_run: false,
+        served_by: None,
+        timing: Some(RequestTiming {
+            setup_ms,
+            queue_wait_ms: None,
+            ttfb_ms: Some(duration_ms(network_time)),
+            download_ms: Some(duration_ms(download_time)),
+            total_ms: duration_ms(total_time),
+        }),
+        remote_addr,
+        attempts: *attempts,
+    };
+
+    // 成功した GET レスポンスのみキャッシュする
+    if let (Some(key), Some(ttl)) = (cache_key, cache_ttl) {
+        if (200..300).contains(&status) {
+            response_cache::store(key, &response, etag, ttl);
+            log::info!("[Request {}] Response cached for {:?}", request_id, ttl);
+        }
+    }
+
+    Ok(response)
+}
+
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 50 * 1024 * 1024; // 50MB制限
+const MAX_RESPONSE_BYTES_LIMIT: usize = 1024 * 1024 * 1024; // 指定できる上限（1GB）
+// Content-Length を信用して確保する最大サイズ（超える分は受信に合わせて伸ばす）
+const MAX_RESPONSE_PREALLOCATION: usize = 1024 * 1024;
+// 大きなレスポンスとして警告する、上限に対する割合
+const LARGE_RESPONSE_WARNING_PERCENT: usize = 20;
+
+fn effective_max_response_bytes(max_response_bytes: Option<u64>) -> usize {
+    max_response_bytes
+        .filter(|max_bytes| *max_bytes > 0)
+        .map(|max_bytes| usize::try_from(max_bytes).unwrap_or(usize::MAX).min(MAX_RESPONSE_BYTES_LIMIT))
+        .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES)
+}
+
+/// 警告しきい値（set_log_limits で指定がなければ上限の 20%）
+fn large_response_warning_bytes(limits: &LogLimits, max_response_bytes: usize) -> usize {
+    limits
+        .large_response_warning_bytes
+        .unwrap_or(max_response_bytes / 100 * LARGE_RESPONSE_WARNING_PERCENT)
+}
+// response-progress を通知する受信量の間隔
+const RESPONSE_PROGRESS_INTERVAL_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ResponseProgress {
+    pub progress_id: String,
+    pub bytes_received: u64,
+    pub total_bytes: Option<u64>, // Content-Length がある場合のみ
+}
+
+fn response_too_large_error(request_id: &Uuid, size: u64, limit: usize) -> String {
+    let err_msg = format!(
+        "[Request {}] Response too large: {} bytes (limit: {} bytes)",
+        request_id, size, limit
+    );
+    log::error!("{}", err_msg);
+    err_msg
+}
+
+/// ログ・エラー表示用にバイト数を読みやすい単位に変換する
+fn format_byte_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    let size = bytes as f64;
+    if size >= MB {
+        format!("{:.1}MB", size / MB)
+    } else if size >= KB {
+        format!("{:.1}KB", size / KB)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// 受信途中で接続が切れた場合のエラー（受信済みのバイト数と Content-Length を含める）
+fn truncated_body_error(request_id: &Uuid, received: usize, total_bytes: Option<u64>, cause: &reqwest::Error) -> String {
+    let err_msg = match total_bytes {
+        Some(total_bytes) => format!(
+            "[Request {}] Response body truncated: received {} ({} bytes) of an expected {} ({} bytes) before the connection dropped: {}",
+            request_id,
+            format_byte_size(received as u64),
+            received,
+            format_byte_size(total_bytes),
+            total_bytes,
+            cause
+        ),
+        None => format!(
+            "[Request {}] Response body truncated: received {} ({} bytes, no Content-Length) before the connection dropped: {}",
+            request_id,
+            format_byte_size(received as u64),
+            received,
+            cause
+        ),
+    };
+    log::error!("{}", err_msg);
+    err_msg
+}
+
+/// 受信し終えたボディを文字列に変換する
+///
+/// 受信が途中で切れた場合は read_response_body がエラーにするため、ここで UTF-8 として
+/// 不正なのはボディ自体が壊れている場合。バイナリのダウンロードもあるため置換して続行する。
+fn decode_response_body(body_bytes: Vec<u8>, request_id: &Uuid) -> String {
+    match String::from_utf8(body_bytes) {
+        Ok(body) => body,
+        Err(err) => {
+            let utf8_error = err.utf8_error();
+            let total = err.as_bytes().len();
+            log::warn!(
+                "[Request {}] Response body is not valid UTF-8 at byte {} of {} (fully received, not truncated); invalid sequences were replaced",
+                request_id,
+                utf8_error.valid_up_to(),
+                total
+            );
+            String::from_utf8_lossy(err.as_bytes()).into_owned()
+        }
+    }
+}
+
+const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// JSON Pointer の 1 要素分を取り出す（`~1` → `/`、`~0` → `~`）
+fn pointer_tokens(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// ポインターに一致した値を削除・マスクし、処理した件数を返す
+fn redact_pointer(value: &mut serde_json::Value, tokens: &[String], mode: RedactionMode) -> usize {
+    use serde_json::Value;
+
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return 0,
+    };
+
+    if !rest.is_empty() {
+        return match (value, token.as_str()) {
+            (Value::Object(map), "*") => map.values_mut().map(|child| redact_pointer(child, rest, mode)).sum(),
+            (Value::Array(items), "*") => items.iter_mut().map(|child| redact_pointer(child, rest, mode)).sum(),
+            (Value::Object(map), key) => map.get_mut(key).map_or(0, |child| redact_pointer(child, rest, mode)),
+            (Value::Array(items), index) => index
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .map_or(0, |child| redact_pointer(child, rest, mode)),
+            _ => 0,
+        };
+    }
+
+    let mask = || Value::String(REDACTED_VALUE.to_string());
+    match (value, token.as_str(), mode) {
+        (Value::Object(map), "*", RedactionMode::Remove) => {
+            let count = map.len();
+            map.clear();
+            count
+        }
+        (Value::Array(items), "*", RedactionMode::Remove) => {
+            let count = items.len();
+            items.clear();
+            count
+        }
+        (Value::Object(map), "*", RedactionMode::Mask) => {
+            map.values_mut().for_each(|child| *child = mask());
+            map.len()
+        }
+        (Value::Array(items), "*", RedactionMode::Mask) => {
+            items.iter_mut().for_each(|child| *child = mask());
+            items.len()
+        }
+        (Value::Object(map), key, RedactionMode::Remove) => usize::from(map.remove(key).is_some()),
+        (Value::Object(map), key, RedactionMode::Mask) => match map.get_mut(key) {
+            Some(child) => {
+                *child = mask();
+                1
+            }
+            None => 0,
+        },
+        (Value::Array(items), index, mode) => match index.parse::<usize>().ok().filter(|index| *index < items.len()) {
+            Some(index) if mode == RedactionMode::Remove => {
+                items.remove(index);
+                1
+            }
+            Some(index) => {
+                items[index] = mask();
+                1
+            }
+            None => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// config.pkg で指定された項目を JSON のレスポンスボディから取り除く（JSON 以外はそのまま返す）
+fn redact_response_body(body: String, redaction: &ResponseRedactionConfig, request_id: &Uuid) -> String {
+    if redaction.pointers.is_empty() {
+        return body;
+    }
+    let mut value: serde_json::Value = match serde_json::from_str(&body) {
+        Ok(value) => value,
+        Err(_) => return body,
+    };
+
+    let redacted: usize = redaction
+        .pointers
+        .iter()
+        .map(|pointer| redact_pointer(&mut value, &pointer_tokens(pointer), redaction.mode))
+        .sum();
+    if redacted == 0 {
+        return body;
+    }
+
+    log::info!("[Request {}] Response redaction applied: {} value(s) {:?}", request_id, redacted, redaction.mode);
+    serde_json::to_string(&value).unwrap_or(body)
+}
+
+/// レスポンスボディをチャンク単位で受信する
+///
+/// 上限を超えた時点で受信を打ち切るため、巨大なレスポンスを丸ごとメモリに載せない。
+async fn read_response_body<F>(
+    response: reqwest::Response,
+    max_bytes: usize,
+    progress_id: Option<&str>,
+    on_progress: &F,
+    request_id: &Uuid,
+) -> Result<Vec<u8>, String>
+where
+    F: Fn(ResponseProgress),
+{
+    use futures_util::StreamExt;
+
+    let total_bytes = response.content_length();
+    if let Some(total_bytes) = total_bytes.filter(|total_bytes| *total_bytes > max_bytes as u64) {
+        return Err(response_too_large_error(request_id, total_bytes, max_bytes));
+    }
+
+    let report = |bytes_received: usize| {
+        if let Some(progress_id) = progress_id {
+            on_progress(ResponseProgress {
+                progress_id: progress_id.to_string(),
+                bytes_received: bytes_received as u64,
+                total_bytes,
+            });
+        }
+    };
+
+    let preallocate = total_bytes.map_or(0, |total_bytes| total_bytes.min(MAX_RESPONSE_PREALLOCATION as u64) as usize);
+    let mut body = Vec::with_capacity(preallocate);
+    let mut reported_bytes = 0;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| truncated_body_error(request_id, body.len(), total_bytes, &e))?;
+        if body.len() + chunk.len() > max_bytes {
+            return Err(response_too_large_error(request_id, (body.len() + chunk.len()) as u64, max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+
+        if body.len() - reported_bytes >= RESPONSE_PROGRESS_INTERVAL_BYTES {
+            report(body.len());
+            reported_bytes = body.len();
+        }
+    }
+    if reported_bytes != body.len() || body.is_empty() {
+        report(body.len());
+    }
+
+    Ok(body)
+}
+
+// SSE の 1 行がこれを超えた場合は異常な応答として打ち切る
+const MAX_SSE_LINE_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StreamChunk {
+    pub stream_id: String,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StreamDone {
+    pub stream_id: String,
+    pub status: u16,
+    pub usage: Option<TokenUsage>, // usage を含むチャンクがないまま終了した場合は None
+}
+
+/// SSE の data から usage を取り出す
+///
+/// Chat Completions（`stream_options.include_usage`）は最終チャンク直下の `usage`、
+/// Responses API は `response.completed` の `response.usage` に含まれる。
+fn parse_stream_usage(data: &str) -> Option<TokenUsage> {
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    let usage = value
+        .get("usage")
+        .filter(|usage| usage.is_object())
+        .or_else(|| value.get("response")?.get("usage").filter(|usage| usage.is_object()))?;
+    serde_json::from_value(usage.clone()).ok()
+}
+
+/// ストリーミング（SSE）でリクエストを送信し、data 行ごとに `on_chunk` を呼び出す
+///
+/// 2xx 以外の応答はボディを読み切ってエラーとして返す。
+pub async fn stream_openai_request<F>(
+    request: OpenAIRequest,
+    stream_id: String,
+    on_chunk: F,
+) -> Result<StreamDone, String>
+where
+    F: Fn(StreamChunk) + Send + Sync,
+{
+    let request_id = Uuid::new_v4();
+    let start_time = Instant::now();
+    let request = apply_provider_profile(request, &request_id)?;
+    enforce_request_features(&request, &secure_config::active_feature_restrictions(), &request_id)?;
+    let bytes_sent = request.body_len() as u64;
+
+    let trace_config = request_trace::current_config();
+    let trace = trace_config
+        .enabled
+        .then(|| start_trace_entry(&request, &request_id, trace_config.include_bodies));
+
+    let group_id = request.group_id.clone();
+    let mut stats = StreamStats::default();
+    let result = run_cancellable_in_group(
+        request_id,
+        group_id,
+        send_stream_request(request, stream_id, request_id, &mut stats, on_chunk),
+    )
+    .await;
+
+    metrics::record_outcome(
+        stats.status.map(|status| (status, stats.bytes_received)),
+        bytes_sent,
+        start_time.elapsed(),
+    );
+    if let Some(entry) = trace {
+        // ストリームはボディを保持しないため、ステータス・usage・接続先のみ記録する
+        let summary = result
+            .as_ref()
+            .map(|done| OpenAIResponse {
+                status: done.status,
+                body: String::new(),
+                headers: HashMap::new(),
+                usage: done.usage.clone(),
+                error_details: None,
+                auth_error: None,
+                dry_run: false,
+                served_by: None,
+                timing: None,
+                remote_addr: stats.remote_addr.clone(),
+                attempts: 1,
+            })
+            .map_err(Clone::clone);
+        request_trace::record(finish_trace_entry(entry, &summary, trace_config.include_bodies));
+    }
+    result
+}
+
+/// メトリクス・記録用に集計するストリームの受信状況（応答がなければ status は None）
+#[derive(Default)]
+struct StreamStats {
+    status: Option<u16>,
+    bytes_received: u64,
+    remote_addr: Option<String>,
+}
+
+async fn send_stream_request<F>(
+    request: OpenAIRequest,
+    stream_id: String,
+    request_id: Uuid,
+    stats: &mut StreamStats,
+    on_chunk: F,
+) -> Result<StreamDone, String>
+where
+    F: Fn(StreamChunk) + Send + Sync,
+{
+    use futures_util::StreamExt;
+
+    let start_time = Instant::now();
+    log::info!("[Request {}] Starting streaming request (stream {})", request_id, stream_id);
+    let deadline_ms = request.overall_deadline_ms.filter(|ms| *ms > 0);
+    let deadline = deadline_ms.map(|ms| start_time + Duration::from_millis(ms));
+    let deadline_error = || deadline_exceeded_error(&request_id, deadline_ms.unwrap_or_default());
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let url = resolve_request_url(&request, allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client_config = request_client_config(&request, &url, &request_id)?;
+    let client = cached_client(&client_config, &request_id)?;
+
+    let api_key = resolve_request_api_key(&request, &request_id).await?;
+
+    let mut builtin_headers = HeaderMap::new();
+    builtin_headers.insert(reqwest::header::ACCEPT, HeaderValue::from_static("text/event-stream"));
+    let req_builder = method_request_builder(&client, &request.method, &url)?;
+    let req_builder = apply_request_body(req_builder, &request, &mut builtin_headers, &request_id)?.headers(builtin_headers);
+    let req_builder = apply_auth_headers(
+        req_builder,
+        &request.additional_headers,
+        &api_key,
+        request.auth_header.as_deref(),
+        &request_id,
+    );
+
+    // 遮断中のベースURLには送信しない（通常のリクエストと同じ状態を共有する）
+    let breaker_key = circuit_key(&request, &url, allow_insecure_http);
+    circuit_breaker::check(&breaker_key).map_err(|err| {
+        let err_msg = format!("[Request {}] {}", request_id, err);
+        log::warn!("{}", err_msg);
+        err_msg
+    })?;
+
+    log::info!("[Request {}] {} {} (stream)", request_id, request.method.to_uppercase(), url);
+    let response = with_deadline(deadline, req_builder.send())
+        .await
+        .ok_or_else(deadline_error)?
+        .map_err(|e| {
+            circuit_breaker::record_failure(&breaker_key);
+            let err_msg = describe_send_error(&request_id, &e, start_time.elapsed());
+            log::error!("{}", err_msg);
+            err_msg
+        })?;
+
+    let status = response.status().as_u16();
+    stats.status = Some(status);
+    stats.remote_addr = response.remote_addr().map(|addr| addr.to_string());
+    if status >= 500 {
+        circuit_breaker::record_failure(&breaker_key);
+    } else {
+        circuit_breaker::record_success(&breaker_key);
+    }
+    if !response.status().is_success() {
+        let body = with_deadline(deadline, response.text()).await.and_then(Result::ok).unwrap_or_default();
+        stats.bytes_received = body.len() as u64;
+        let message = parse_error_details(status, &body)
+            .and_then(|details| details.message)
+            .unwrap_or_else(|| log_preview(&body));
+        let err_msg = format!("[Request {}] Streaming request failed ({}): {}", request_id, status, message);
+        log::error!("{}", err_msg);
+        return Err(err_msg);
+    }
+
+    let mut usage = None;
+    let mut chunk_count = 0usize;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut body_stream = response.bytes_stream();
+
+    'read: loop {
+        let next = with_deadline(deadline, body_stream.next()).await.ok_or_else(deadline_error)?;
+        let at_eof = match next {
+            Some(bytes) => {
+                let bytes = bytes.map_err(|e| {
+                    let err_msg = describe_send_error(&request_id, &e, start_time.elapsed());
+                    log::error!("[Request {}] Stream interrupted after {} chunk(s): {}", request_id, chunk_count, err_msg);
+                    err_msg
+                })?;
+                stats.bytes_received += bytes.len() as u64;
+                buffer.extend_from_slice(&bytes);
+                false
+            }
+            // 末尾に改行のない最後の行も 1 行として処理する
+            None if buffer.is_empty() => break,
+            None => {
+                buffer.push(b'\n');
+                true
+            }
+        };
+
+        // 改行までを 1 行として処理する（マルチバイト文字がチャンク境界で分かれても壊さない）
+        while let Some(newline) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let data = match line.trim_end().strip_prefix("data:") {
+                Some(data) => data.trim_start(),
+                None => continue,
+            };
+            if data == "[DONE]" {
+                break 'read;
+            }
+            if let Some(parsed) = parse_stream_usage(data) {
+                usage = Some(parsed);
+            }
+            chunk_count += 1;
+            on_chunk(StreamChunk {
+                stream_id: stream_id.clone(),
+                data: data.to_string(),
+            });
+        }
+
+        if at_eof {
+            break;
+        }
+        if buffer.len() > MAX_SSE_LINE_BYTES {
+            let err_msg = format!(
+                "[Request {}] Stream line exceeded {} bytes without a newline",
+                request_id, MAX_SSE_LINE_BYTES
+            );
+            log::error!("{}", err_msg);
+            return Err(err_msg);
+        }
+    }
+
+    if usage.is_none() {
+        log::debug!("[Request {}] Stream ended without a usage chunk", request_id);
+    }
+    log::info!(
+        "[Request {}] Stream complete | Status: {} | Chunks: {} | Total: {:?}",
+        request_id, status, chunk_count, start_time.elapsed()
+    );
+
+    Ok(StreamDone {
+        stream_id,
+        status,
+        usage,
+    })
+}
+
+/// フロントエンドが付けることのある data URI の接頭辞（data:...;base64,）を取り除く
+fn strip_data_uri_prefix(file_data: &str) -> &str {
+    let trimmed = file_data.trim();
+    // バイト位置で切るとマルチバイト文字の途中で panic するため get で取り出す
+    if trimmed.get(..5).is_some_and(|prefix| prefix.eq_ignore_ascii_case("data:")) {
+        if let Some(comma) = trimmed.find(',') {
+            return &trimmed[comma + 1..];
+        }
+    }
+    trimmed
+}
+
+/// Base64 文字列の長さからデコード後のバイト数を見積もる
+fn estimated_decoded_size(encoded: &str) -> u64 {
+    let len = encoded.len() as u64;
+    let padding = encoded.bytes().rev().take_while(|byte| *byte == b'=').count() as u64;
+    let remainder = match len % 4 {
+        2 => 1,
+        3 => 2,
+        _ => 0,
+    };
+    (len / 4 * 3 + remainder).saturating_sub(padding)
+}
+
+/// サイズを事前に確認してから Base64 をデコードする（巨大な入力で大量のメモリを確保しないため）
+fn decode_base64_file(file_data: &str, file_name: &str, max_bytes: u64) -> Result<Vec<u8>, String> {
+    let encoded = strip_data_uri_prefix(file_data);
+    if encoded.is_empty() {
+        return Err(format!("ファイル {} のデータが空です", file_name));
+    }
+
+    let estimated = estimated_decoded_size(encoded);
+    if estimated > max_bytes {
+        return Err(format!(
+            "ファイル {} が大きすぎます: 約 {} bytes（上限: {} bytes）",
+            file_name, estimated, max_bytes
+        ));
+    }
+
+    general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("ファイル {} の Base64 デコードに失敗しました: {}", file_name, e))
+}
+
+/// purpose を正規化（前後の空白除去・小文字化）し、既知の値か確認する
+fn normalize_purpose(purpose: &str, allow_unknown: bool, request_id: &Uuid) -> Result<String, String> {
+    let normalized = purpose.trim().to_ascii_lowercase();
+    if normalized.is_empty() {
+        return Err(format!("[Request {}] purpose が指定されていません", request_id));
+    }
+    if KNOWN_UPLOAD_PURPOSES.contains(&normalized.as_str()) {
+        return Ok(normalized);
+    }
+    if allow_unknown {
+        log::warn!("[Request {}] Sending unknown upload purpose: {}", request_id, normalized);
+        return Ok(normalized);
+    }
+
+    let err_msg = format!(
+        "[Request {}] purpose が正しくありません: {} (有効な値: {})",
+        request_id, purpose, KNOWN_UPLOAD_PURPOSES.join(", ")
+    );
+    log::error!("{}", err_msg);
+    Err(err_msg)
+}
+
+/// アップロード前に gzip 圧縮する（ファイル名に .gz を付け、MIME を application/gzip にする）
+///
+/// プロバイダーは圧縮済みの入力を自動では展開しないため、gzip を受け付けるエンドポイント・purpose 専用。
+async fn gzip_for_upload(
+    file_bytes: Vec<u8>,
+    file_name: &str,
+    request_id: &Uuid,
+) -> Result<(Vec<u8>, String), String> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let original_size = file_bytes.len();
+    let compressed = tokio::task::spawn_blocking(move || {
+        let mut encoder = GzEncoder::new(Vec::with_capacity(original_size / 4), Compression::default());
+        encoder.write_all(&file_bytes)?;
+        encoder.finish()
+    })
+    .await
+    .map_err(|e| format!("[Request {}] Failed to compress {}: {}", request_id, file_name, e))?
+    .map_err(|e| format!("[Request {}] Failed to compress {}: {}", request_id, file_name, e))?;
+
+    log::info!(
+        "[Request {}] Compressed {} with gzip: {} bytes -> {} bytes",
+        request_id, file_name, original_size, compressed.len()
+    );
+
+    let gzip_file_name = if file_name.to_ascii_lowercase().ends_with(".gz") {
+        file_name.to_string()
+    } else {
+        format!("{}.gz", file_name)
+    };
+    Ok((compressed, gzip_file_name))
+}
+
+pub async fn upload_file_to_openai(request: FileUploadRequest) -> Result<OpenAIResponse, String> {
+    let request_id = Uuid::new_v4();
+    enforce_feature(
+        RestrictedFeature::FileUpload,
+        &secure_config::active_feature_restrictions(),
+        &mask_api_key(&request.api_key),
+        "upload_file",
+        &request_id,
+    )?;
+    let start_time = Instant::now();
+    let bytes_sent = estimated_decoded_size(strip_data_uri_prefix(&request.file_data));
+
+    let result = run_cancellable(request_id, send_file_upload(request, request_id)).await;
+    metrics::record_request(&result, bytes_sent, start_time.elapsed());
+    result
+}
+
+async fn send_file_upload(request: FileUploadRequest, request_id: Uuid) -> Result<OpenAIResponse, String> {
+    let start_time = Instant::now();
+
+    log::info!("[Request {}] Starting file upload: {}", request_id, request.file_name);
+
+    let purpose = normalize_purpose(&request.purpose, request.allow_unknown_purpose.unwrap_or(false), &request_id)?;
+
+    // URLを構築（クライアント作成前に検証する）
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let normalized_base_url = normalize_base_url(&request.base_url, allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] Base URL validation failed: {}", request_id, err);
+        err
+    })?;
+    let url = format!("{}/files", normalized_base_url);
+
+    // プロキシ設定を適用したクライアントを作成（大きなファイルは数分かかるため長めの既定値）
+    let connect_timeout_ms = request.connect_timeout_ms.unwrap_or(DEFAULT_UPLOAD_CONNECT_TIMEOUT_MS);
+    let upload_timeout_ms = request.upload_timeout_ms.unwrap_or(DEFAULT_UPLOAD_TIMEOUT_MS);
+    log::debug!(
+        "[Request {}] Upload timeouts | Connect: {}ms | Overall: {}ms",
+        request_id, connect_timeout_ms, upload_timeout_ms
+    );
+    let client_config = ClientConfig {
+        connect_timeout_ms: Some(connect_timeout_ms),
+        use_system_certs: request.use_system_certs.unwrap_or(false),
+        min_tls_version: request.min_tls_version.clone(),
+        ..ClientConfig::new(&request.proxy_config, Some(upload_timeout_ms))
+    };
+    let client = cached_client(&client_config, &request_id)?;
+
+    // Base64デコード（サイズを確認してから）
+    let max_upload_bytes = request.max_upload_bytes.unwrap_or(DEFAULT_MAX_UPLOAD_BYTES);
+    let file_bytes = decode_base64_file(&request.file_data, &request.file_name, max_upload_bytes).map_err(|err| {
+        let err_msg = format!("[Request {}] {}", request_id, err);
+        log::error!("{}", err_msg);
+        err_msg
+    })?;
+
+    log::info!("[Request {}] File size: {} bytes", request_id, file_bytes.len());
+
+    let (file_bytes, file_name, mime_type) = if request.gzip_before_upload.unwrap_or(false) {
+        let (compressed, gzip_file_name) = gzip_for_upload(file_bytes, &request.file_name, &request_id).await?;
+        (compressed, gzip_file_name, "application/gzip")
+    } else {
+        (file_bytes, request.file_name.clone(), "application/octet-stream")
+    };
+
+    // multipart/form-data を作成
+    let file_part = multipart::Part::bytes(file_bytes)
+        .file_name(file_name)
+        .mime_str(mime_type)
+        .map_err(|e| format!("[Request {}] Failed to create file part: {}", request_id, e))?;
+
+    let form = multipart::Form::new()
+        .part("file", file_part)
+        .text("purpose", purpose);
+
+    // リクエストを送信
+    log::info!("[Request {}] Uploading to {}", request_id, url);
+    let req_builder = apply_upload_headers(
+        client.post(&url).multipart(form),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    let send_start = Instant::now();
+    let response = req_builder.send().await.map_err(|e| {
+        let err_msg = describe_send_error(&request_id, &e, start_time.elapsed());
+        log::error!("[Request {}] Upload failed after {:?}: {}", request_id, start_time.elapsed(), err_msg);
+        err_msg
+    })?;
+
+    let status = response.status().as_u16();
+    let network_time = send_start.elapsed();
+
+    // レスポンスヘッダーを取得
+    let mut headers = HashMap::new();
+    for (key, value) in response.headers() {
+        if let Ok(value_str) = value.to_str() {
+            headers.insert(key.to_string(), value_str.to_string());
+        }
+    }
+
+    // レスポンスボディを取得
+    let body = response
+        .text()
+        .await
+        .map_err(|e| {
+            let err_msg = describe_send_error(&request_id, &e, start_time.elapsed());
+            log::error!("[Request {}] Failed to read upload response after {:?}: {}", request_id, start_time.elapsed(), err_msg);
+            err_msg
+        })?;
+
+    let total_time = start_time.elapsed();
+
+    log::info!(
+        "[Request {}] Upload complete | Status: {} | Network: {:?} | Total: {:?}",
+        request_id, status, network_time, total_time
+    );
+
+    if status >= 400 {
+        log::error!("[Request {}] Upload error ({}): {}", request_id, status, log_preview(&body));
+    }
+
+    Ok(OpenAIResponse {
+        status,
+        error_details: parse_error_details(status, &body),
+        auth_error: AuthError::from_status(status),
+        body,
+        headers,
+        usage: None,
+        dry_run: false,
+        served_by: None,
+        timing: None,
+        remote_addr: None,
+        attempts: 1,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateUploadRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub file_name: String,
+    pub purpose: String,
+    pub bytes: u64,
+    pub mime_type: String,
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub timeout_ms: Option<u64>,
+    pub allow_unknown_purpose: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadPartRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub upload_id: String,
+    pub file_path: String, // ローカルファイルのパス
+    pub offset: u64,
+    pub length: u64,
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub timeout_ms: Option<u64>,
+    pub min_tls_version: Option<String>, // 許可する最小の TLS バージョン（"1.0"〜"1.3"）
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompleteUploadRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub upload_id: String,
+    pub part_ids: Vec<String>,
+    pub md5: Option<String>,
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UploadProgress {
+    pub upload_id: String,
+    pub offset: u64,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+}
+
+/// 進行中のアップロードセッション（終了時にキャンセルするための接続情報と送信済みパート）
+#[derive(Debug, Clone)]
+struct UploadSession {
+    base_url: String,
+    api_key: String,
+    proxy_config: Option<ProxyConfig>,
+    additional_headers: Option<HashMap<String, String>>,
+    allow_insecure_http: bool,
+    part_ids: Vec<String>,
+}
+
+static UPLOAD_SESSIONS: Lazy<Mutex<HashMap<String, UploadSession>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AbortUploadResult {
+    pub upload_id: String,
+    pub cancelled: bool,             // false の場合は完了済み・不明なセッションとして何もしなかった
+    pub status: Option<u16>,
+    pub discarded_parts: usize,      // 破棄したローカルのパート記録数
+}
+
+/// 成功レスポンスのボディから "id" を取り出す
+fn response_object_id(response: &OpenAIResponse) -> Option<String> {
+    if !(200..300).contains(&response.status) {
+        return None;
+    }
+    serde_json::from_str::<serde_json::Value>(&response.body)
+        .ok()?
+        .get("id")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn take_upload_session(upload_id: &str) -> Option<UploadSession> {
+    UPLOAD_SESSIONS.lock().ok()?.remove(upload_id)
+}
+
+fn upload_session(upload_id: &str) -> Option<UploadSession> {
+    UPLOAD_SESSIONS.lock().ok()?.get(upload_id).cloned()
+}
+
+fn validate_upload_id(upload_id: &str) -> Result<(), String> {
+    let valid = !upload_id.is_empty()
+        && upload_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("アップロードIDが正しくありません: {}", upload_id))
+    }
+}
+
+fn apply_upload_headers(
+    req_builder: reqwest::RequestBuilder,
+    additional_headers: &Option<HashMap<String, String>>,
+    api_key: &str,
+    request_id: &Uuid,
+) -> reqwest::RequestBuilder {
+    apply_auth_headers(req_builder, additional_headers, api_key, None, request_id)
+}
+
+fn apply_auth_headers(
+    req_builder: reqwest::RequestBuilder,
+    additional_headers: &Option<HashMap<String, String>>,
+    api_key: &str,
+    auth_header: Option<&str>,
+    request_id: &Uuid,
+) -> reqwest::RequestBuilder {
+    log::info!("[Request {}] API Key: {}", request_id, mask_api_key(api_key));
+
+    let mut builtin_headers = HeaderMap::new();
+    match api_key_header(auth_header, api_key, request_id) {
+        Ok((name, value)) => {
+            builtin_headers.insert(name, value);
+        }
+        // 不正な API キーはヘッダーを付けずに送信し、サーバー側の 401 に任せる
+        Err(err) => log::error!("{}", err),
+    }
+
+    // 追加ヘッダーは組み込みヘッダーの後に上書きで適用する（重複させない）
+    let headers = merge_additional_headers(builtin_headers, additional_headers, request_id);
+    req_builder.headers(headers)
+}
+
+async fn send_upload_request(
+    req_builder: reqwest::RequestBuilder,
+    request_id: &Uuid,
+    start_time: Instant,
+) -> Result<OpenAIResponse, String> {
+    let response = req_builder.send().await.map_err(|e| {
+        let err_msg = describe_send_error(request_id, &e, start_time.elapsed());
+        log::error!("[Request {}] Upload request failed after {:?}: {}", request_id, start_time.elapsed(), err_msg);
+        err_msg
+    })?;
+
+    let status = response.status().as_u16();
+
+    let mut headers = HashMap::new();
+    for (key, value) in response.headers() {
+        if let Ok(value_str) = value.to_str() {
+            headers.insert(key.to_string(), value_str.to_string());
+        }
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| {
+            let err_msg = describe_send_error(request_id, &e, start_time.elapsed());
+            log::error!("[Request {}] Failed to read upload response after {:?}: {}", request_id, start_time.elapsed(), err_msg);
+            err_msg
+        })?;
+
+    log::info!(
+        "[Request {}] Upload request complete | Status: {} | Total: {:?}",
+        request_id, status, start_time.elapsed()
+    );
+
+    if status >= 400 {
+        log::error!("[Request {}] Upload error ({}): {}", request_id, status, log_preview(&body));
+    }
+
+    Ok(OpenAIResponse {
+        status,
+        error_details: parse_error_details(status, &body),
+        auth_error: AuthError::from_status(status),
+        body,
+        headers,
+        usage: None,
+        dry_run: false,
+        served_by: None,
+        timing: None,
+        remote_addr: None,
+        attempts: 1,
+    })
+}
+
+/// Uploads API のアップロードセッションを作成する（POST /uploads）
+pub async fn create_upload(request: CreateUploadRequest) -> Result<OpenAIResponse, String> {
+    let request_id = Uuid::new_v4();
+    enforce_feature(
+        RestrictedFeature::FileUpload,
+        &secure_config::active_feature_restrictions(),
+        &mask_api_key(&request.api_key),
+        "create_upload",
+        &request_id,
+    )?;
+    let session = UploadSession {
+        base_url: request.base_url.clone(),
+        api_key: request.api_key.clone(),
+        proxy_config: request.proxy_config.clone(),
+        additional_headers: request.additional_headers.clone(),
+        allow_insecure_http: request.allow_insecure_http.unwrap_or(false),
+        part_ids: Vec::new(),
+    };
+    let result = run_cancellable(request_id, send_create_upload(request, request_id)).await;
+
+    // 途中で放棄された場合にキャンセルできるよう、作成したセッションを記録する
+    if let Some(upload_id) = result.as_ref().ok().and_then(response_object_id) {
+        if let Ok(mut sessions) = UPLOAD_SESSIONS.lock() {
+            sessions.insert(upload_id, session);
+        }
+    }
+    result
+}
+
+async fn send_create_upload(request: CreateUploadRequest, request_id: Uuid) -> Result<OpenAIResponse, String> {
+    let start_time = Instant::now();
+
+    log::info!(
+        "[Request {}] Creating upload session: {} ({} bytes)",
+        request_id, request.file_name, request.bytes
+    );
+
+    let purpose = normalize_purpose(&request.purpose, request.allow_unknown_purpose.unwrap_or(false), &request_id)?;
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let url = build_request_url(&request.base_url, "uploads", allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client = cached_client(&ClientConfig::new(&request.proxy_config, request.timeout_ms), &request_id)?;
+
+    let body = serde_json::json!({
+        "filename": request.file_name,
+        "purpose": purpose,
+        "bytes": request.bytes,
+        "mime_type": request.mime_type,
+    });
+
+    let req_builder = apply_upload_headers(
+        client.post(&url).json(&body),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    send_upload_request(req_builder, &request_id, start_time).await
+}
+
+/// ローカルファイルの指定範囲を 1 パートとして送信する（POST /uploads/{id}/parts）
+///
+/// 失敗したパートだけを再送できるよう、パートごとに独立したリクエストとして扱う。
+pub async fn upload_part<F>(request: UploadPartRequest, on_progress: F) -> Result<OpenAIResponse, String>
+where
+    F: Fn(UploadProgress) + Send + Sync + 'static,
+{
+    let request_id = Uuid::new_v4();
+    enforce_feature(
+        RestrictedFeature::FileUpload,
+        &secure_config::active_feature_restrictions(),
+        &mask_api_key(&request.api_key),
+        "upload_part",
+        &request_id,
+    )?;
+    let upload_id = request.upload_id.clone();
+    let result = run_cancellable(request_id, send_upload_part(request, request_id, on_progress)).await;
+
+    if let Some(part_id) = result.as_ref().ok().and_then(response_object_id) {
+        if let Ok(mut sessions) = UPLOAD_SESSIONS.lock() {
+            if let Some(session) = sessions.get_mut(&upload_id) {
+                session.part_ids.push(part_id);
+            }
+        }
+    }
+    result
+}
+
+async fn send_upload_part<F>(request: UploadPartRequest, request_id: Uuid, on_progress: F) -> Result<OpenAIResponse, String>
+where
+    F: Fn(UploadProgress) + Send + Sync + 'static,
+{
+    use futures_util::StreamExt;
+
+    let start_time = Instant::now();
+
+    log::info!(
+        "[Request {}] Uploading part for {} (offset: {}, length: {})",
+        request_id, request.upload_id, request.offset, request.length
+    );
+
+    validate_upload_id(&request.upload_id)?;
+
+    if request.length == 0 || request.length > MAX_UPLOAD_PART_SIZE {
+        return Err(format!(
+            "[Request {}] Invalid part length: {} bytes (must be 1..={} bytes)",
+            request_id, request.length, MAX_UPLOAD_PART_SIZE
+        ));
+    }
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let path = format!("uploads/{}/parts", request.upload_id);
+    let url = build_request_url(&request.base_url, &path, allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    // ファイルから指定範囲を読み込む
+    let mut file = tokio::fs::File::open(&request.file_path)
+        .await
+        .map_err(|e| format!("[Request {}] Failed to open file {}: {}", request_id, request.file_path, e))?;
+    let file_len = file
+        .metadata()
+        .await
+        .map_err(|e| format!("[Request {}] Failed to read file metadata: {}", request_id, e))?
+        .len();
+
+    let end = request.offset.saturating_add(request.length);
+    if end > file_len {
+        return Err(format!(
+            "[Request {}] Part range {}..{} exceeds file size {} bytes",
+            request_id, request.offset, end, file_len
+        ));
+    }
+
+    file.seek(SeekFrom::Start(request.offset))
+        .await
+        .map_err(|e| format!("[Request {}] Failed to seek file: {}", request_id, e))?;
+
+    let client_config = ClientConfig {
+        min_tls_version: request.min_tls_version.clone(),
+        ..ClientConfig::new(&request.proxy_config, request.timeout_ms)
+    };
+    let client = cached_client(&client_config, &request_id)?;
+
+    // 指定範囲をファイルから直接チャンク単位でストリーミングし、送信済みバイト数を通知する（パート全体をメモリに載せない）
+    let total_bytes = request.length;
+    let upload_id = request.upload_id.clone();
+    let offset = request.offset;
+    let mut bytes_sent: u64 = 0;
+    let stream = ReaderStream::with_capacity(file.take(total_bytes), UPLOAD_PROGRESS_CHUNK_SIZE).map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            bytes_sent += chunk.len() as u64;
+            on_progress(UploadProgress {
+                upload_id: upload_id.clone(),
+                offset,
+                bytes_sent,
+                total_bytes,
+            });
+        }
+        chunk
+    });
+
+    let data_part = multipart::Part::stream_with_length(Body::wrap_stream(stream), total_bytes)
+        .file_name("part")
+        .mime_str("application/octet-stream")
+        .map_err(|e| format!("[Request {}] Failed to create part: {}", request_id, e))?;
+    let form = multipart::Form::new().part("data", data_part);
+
+    let req_builder = apply_upload_headers(
+        client.post(&url).multipart(form),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    send_upload_request(req_builder, &request_id, start_time).await
+}
+
+/// 送信済みパートを確定してファイルを作成する（POST /uploads/{id}/complete）
+pub async fn complete_upload(request: CompleteUploadRequest) -> Result<OpenAIResponse, String> {
+    let request_id = Uuid::new_v4();
+    enforce_feature(
+        RestrictedFeature::FileUpload,
+        &secure_config::active_feature_restrictions(),
+        &mask_api_key(&request.api_key),
+        "complete_upload",
+        &request_id,
+    )?;
+    let upload_id = request.upload_id.clone();
+    let result = run_cancellable(request_id, send_complete_upload(request, request_id)).await;
+
+    if matches!(&result, Ok(response) if (200..300).contains(&response.status)) {
+        take_upload_session(&upload_id);
+    }
+    result
+}
+
+async fn send_complete_upload(request: CompleteUploadRequest, request_id: Uuid) -> Result<OpenAIResponse, String> {
+    let start_time = Instant::now();
+
+    log::info!(
+        "[Request {}] Completing upload {} ({} parts)",
+        request_id, request.upload_id, request.part_ids.len()
+    );
+
+    validate_upload_id(&request.upload_id)?;
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let path = format!("uploads/{}/complete", request.upload_id);
+    let url = build_request_url(&request.base_url, &path, allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client = cached_client(&ClientConfig::new(&request.proxy_config, request.timeout_ms), &request_id)?;
+
+    let mut body = serde_json::json!({ "part_ids": request.part_ids });
+    if let Some(md5) = &request.md5 {
+        body["md5"] = serde_json::Value::String(md5.clone());
+    }
+
+    let req_builder = apply_upload_headers(
+        client.post(&url).json(&body),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    send_upload_request(req_builder, &request_id, start_time).await
+}
+
+/// アップロードセッションをキャンセルし、ローカルのパート記録を破棄する（POST /uploads/{id}/cancel）
+///
+/// 完了済み・キャンセル済み・不明なセッションの場合は何もせず成功として扱う。
+pub async fn abort_upload(
+    base_url: String,
+    api_key: String,
+    upload_id: String,
+    proxy_config: Option<ProxyConfig>,
+) -> Result<AbortUploadResult, String> {
+    validate_upload_id(&upload_id)?;
+
+    // 記録済みのセッションがあれば作成時の追加ヘッダー・HTTP 許可設定を引き継ぐ
+    let known = upload_session(&upload_id);
+    let session = UploadSession {
+        base_url,
+        api_key,
+        proxy_config,
+        additional_headers: known.as_ref().and_then(|session| session.additional_headers.clone()),
+        allow_insecure_http: known.as_ref().is_some_and(|session| session.allow_insecure_http),
+        part_ids: Vec::new(),
+    };
+
+    let request_id = Uuid::new_v4();
+    run_cancellable(request_id, send_abort_upload(upload_id, session, request_id)).await
+}
+
+async fn send_abort_upload(
+    upload_id: String,
+    session: UploadSession,
+    request_id: Uuid,
+) -> Result<AbortUploadResult, String> {
+    let start_time = Instant::now();
+
+    log::info!("[Request {}] Aborting upload {}", request_id, upload_id);
+
+    let path = format!("uploads/{}/cancel", upload_id);
+    let url = build_request_url(&session.base_url, &path, session.allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client = cached_client(
+        &ClientConfig::new(&session.proxy_config, Some(ABORT_UPLOAD_TIMEOUT_MS)),
+        &request_id,
+    )?;
+    let req_builder = apply_upload_headers(
+        client.post(&url),
+        &session.additional_headers,
+        &session.api_key,
+        &request_id,
+    );
+
+    // 送信に失敗した場合はローカルの記録を残し、終了時に再度キャンセルを試みる
+    let response = send_upload_request(req_builder, &request_id, start_time).await?;
+    let cancelled = match response.status {
+        200..=299 => true,
+        // 完了済み・キャンセル済み（400/409）や不明なセッション（404）は何もしない
+        400 | 404 | 409 => false,
+        status => {
+            return Err(format!(
+                "アップロードのキャンセルに失敗しました ({}): HTTP {}",
+                upload_id, status
+            ))
+        }
+    };
+
+    let discarded_parts = take_upload_session(&upload_id)
+        .map(|session| session.part_ids.len())
+        .unwrap_or(0);
+
+    if cancelled {
+        log::info!(
+            "[Request {}] Upload {} cancelled ({} local part(s) discarded)",
+            request_id, upload_id, discarded_parts
+        );
+    } else {
+        log::info!(
+            "[Request {}] Upload {} was already finished or unknown (status {}), nothing to cancel",
+            request_id, upload_id, response.status
+        );
+    }
+
+    Ok(AbortUploadResult {
+        upload_id,
+        cancelled,
+        status: Some(response.status),
+        discarded_parts,
+    })
+}
+
+/// 進行中のアップロードセッションをすべてキャンセルし、キャンセルできた件数を返す（終了時用）
+pub async fn abort_pending_uploads() -> usize {
+    let sessions: Vec<(String, UploadSession)> = match UPLOAD_SESSIONS.lock() {
+        Ok(sessions) => sessions.iter().map(|(id, session)| (id.clone(), session.clone())).collect(),
+        Err(_) => return 0,
+    };
+    if sessions.is_empty() {
+        return 0;
+    }
+
+    log::info!("Aborting {} pending upload session(s)", sessions.len());
+
+    let aborts = sessions.into_iter().map(|(upload_id, session)| async move {
+        let request_id = Uuid::new_v4();
+        match send_abort_upload(upload_id.clone(), session, request_id).await {
+            Ok(result) => result.cancelled,
+            Err(err) => {
+                log::warn!("Failed to abort upload {} on shutdown: {}", upload_id, err);
+                false
+            }
+        }
+    });
+
+    let timeout = Duration::from_millis(SHUTDOWN_ABORT_UPLOADS_TIMEOUT_MS);
+    match tokio::time::timeout(timeout, futures_util::future::join_all(aborts)).await {
+        Ok(results) => results.into_iter().filter(|cancelled| *cancelled).count(),
+        Err(_) => {
+            log::warn!("Timed out aborting pending upload sessions after {:?}", timeout);
+            0
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub file_data: Option<String>, // Base64 encoded file data
+    pub file_path: Option<String>, // file_data がない場合に読み込むローカルファイル
+    pub file_name: String,
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultiFileUploadRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub files: Vec<FileEntry>,
+    pub purpose: String,
+    pub path: Option<String>,        // 既定は /files
+    pub field_name: Option<String>,  // 既定は "file"
+    pub indexed_field_names: Option<bool>, // true の場合 "file[0]", "file[1]" ... とする
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub timeout_ms: Option<u64>,
+    pub allow_unknown_purpose: Option<bool>,
+    pub gzip_before_upload: Option<bool>, // gzip 圧縮した入力を受け付けるエンドポイント・purpose でのみ指定する
+}
+
+/// ファイルの内容を取得する。失敗した場合はどのファイルかを示すエラーを返す
+async fn read_file_entry(entry: &FileEntry, index: usize, request_id: &Uuid) -> Result<Vec<u8>, String> {
+    match (&entry.file_data, &entry.file_path) {
+        (Some(file_data), _) => decode_base64_file(file_data, &entry.file_name, DEFAULT_MAX_UPLOAD_BYTES)
+            .map_err(|err| format!("[Request {}] File #{}: {}", request_id, index, err)),
+        (None, Some(file_path)) => {
+            let read_error = |e: std::io::Error| {
+                format!(
+                    "[Request {}] Failed to read file #{} ({}) from {}: {}",
+                    request_id, index, entry.file_name, file_path, e
+                )
+            };
+            // Base64 と同じ上限を、読み込む前にファイルサイズで確認する
+            let file_len = tokio::fs::metadata(file_path).await.map_err(read_error)?.len();
+            if file_len > DEFAULT_MAX_UPLOAD_BYTES {
+                return Err(format!(
+                    "[Request {}] File #{}: ファイル {} が大きすぎます: {} bytes（上限: {} bytes）",
+                    request_id, index, entry.file_name, file_len, DEFAULT_MAX_UPLOAD_BYTES
+                ));
+            }
+            tokio::fs::read(file_path).await.map_err(read_error)
+        }
+        (None, None) => Err(format!(
+            "[Request {}] File #{} ({}) has neither file_data nor file_path",
+            request_id, index, entry.file_name
+        )),
+    }
+}
+
+/// 複数ファイルを 1 つの multipart リクエストでアップロードする
+pub async fn upload_files_to_openai(request: MultiFileUploadRequest) -> Result<OpenAIResponse, String> {
+    let request_id = Uuid::new_v4();
+    enforce_feature(
+        RestrictedFeature::FileUpload,
+        &secure_config::active_feature_restrictions(),
+        &mask_api_key(&request.api_key),
+        "upload_files",
+        &request_id,
+    )?;
+    let start_time = Instant::now();
+    let bytes_sent = request
+        .files
+        .iter()
+        .map(|entry| entry.file_data.as_deref().map_or(0, |data| estimated_decoded_size(strip_data_uri_prefix(data))))
+        .sum();
+
+    let result = run_cancellable(request_id, send_multi_file_upload(request, request_id)).await;
+    metrics::record_request(&result, bytes_sent, start_time.elapsed());
+    result
+}
+
+async fn send_multi_file_upload(request: MultiFileUploadRequest, request_id: Uuid) -> Result<OpenAIResponse, String> {
+    let start_time = Instant::now();
+
+    log::info!(
+        "[Request {}] Starting multi-file upload: {} files",
+        request_id, request.files.len()
+    );
+
+    if request.files.is_empty() {
+        return Err(format!("[Request {}] No files to upload", request_id));
+    }
+
+    let purpose = normalize_purpose(&request.purpose, request.allow_unknown_purpose.unwrap_or(false), &request_id)?;
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let path = request.path.as_deref().unwrap_or("files");
+    let url = build_request_url(&request.base_url, path, allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client = cached_client(&ClientConfig::new(&request.proxy_config, request.timeout_ms), &request_id)?;
+
+    let field_name = request.field_name.as_deref().unwrap_or("file");
+    let indexed = request.indexed_field_names.unwrap_or(false);
+    let gzip_before_upload = request.gzip_before_upload.unwrap_or(false);
+    let mut form = multipart::Form::new();
+    for (index, entry) in request.files.iter().enumerate() {
+        let file_bytes = read_file_entry(entry, index, &request_id).await.map_err(|err| {
+            log::error!("{}", err);
+            err
+        })?;
+        log::info!(
+            "[Request {}] File #{} ({}): {} bytes",
+            request_id, index, entry.file_name, file_bytes.len()
+        );
+
+        let (file_bytes, file_name, mime_type) = if gzip_before_upload {
+            let (compressed, gzip_file_name) = gzip_for_upload(file_bytes, &entry.file_name, &request_id).await?;
+            (compressed, gzip_file_name, "application/gzip")
+        } else {
+            let mime_type = entry.mime_type.as_deref().unwrap_or("application/octet-stream");
+            (file_bytes, entry.file_name.clone(), mime_type)
+        };
+        let part = multipart::Part::bytes(file_bytes)
+            .file_name(file_name)
+            .mime_str(mime_type)
+            .map_err(|e| {
+                format!(
+                    "[Request {}] Invalid MIME type for file #{} ({}): {}",
+                    request_id, index, entry.file_name, e
+                )
+            })?;
+
+        let part_name = if indexed {
+            format!("{}[{}]", field_name, index)
+        } else {
+            field_name.to_string()
+        };
+        form = form.part(part_name, part);
+    }
+    form = form.text("purpose", purpose);
+
+    log::info!("[Request {}] Uploading {} files to {}", request_id, request.files.len(), url);
+    let req_builder = apply_upload_headers(
+        client.post(&url).multipart(form),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    send_upload_request(req_builder, &request_id, start_time).await
+}
+
+// Batch API の入力ファイル 1 つあたりのリクエスト数の上限
+const MAX_BATCH_INPUT_LINES: usize = 50_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchInputUploadRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub requests: Option<Vec<serde_json::Value>>, // file_path より優先
+    pub file_path: Option<String>,                // ローカルの .jsonl ファイル
+    pub file_name: Option<String>,                // 既定は batch_input.jsonl
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub timeout_ms: Option<u64>,
+}
+
+/// Batch API の 1 行（custom_id / method / url / body）として正しいかを確認し、custom_id を返す
+fn validate_batch_line(value: &serde_json::Value) -> Result<&str, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| "JSON オブジェクトではありません".to_string())?;
+
+    let custom_id = object
+        .get("custom_id")
+        .and_then(|id| id.as_str())
+        .filter(|id| !id.trim().is_empty())
+        .ok_or_else(|| "custom_id がありません".to_string())?;
+    if !object
+        .get("method")
+        .and_then(|method| method.as_str())
+        .is_some_and(|method| method.eq_ignore_ascii_case("POST"))
+    {
+        return Err("method は POST である必要があります".to_string());
+    }
+    if !object
+        .get("url")
+        .and_then(|url| url.as_str())
+        .is_some_and(|url| url.starts_with("/v1/"))
+    {
+        return Err("url は /v1/ で始まるパスである必要があります".to_string());
+    }
+    if !object.get("body").is_some_and(|body| body.is_object()) {
+        return Err("body がオブジェクトではありません".to_string());
+    }
+    Ok(custom_id)
+}
+
+fn check_batch_line(
+    value: &serde_json::Value,
+    label: &str,
+    seen_ids: &mut HashSet<String>,
+    request_id: &Uuid,
+) -> Result<(), String> {
+    let custom_id = validate_batch_line(value)
+        .map_err(|err| format!("[Request {}] {}: {}", request_id, label, err))?;
+    if !seen_ids.insert(custom_id.to_string()) {
+        return Err(format!("[Request {}] {}: custom_id が重複しています: {}", request_id, label, custom_id));
+    }
+    Ok(())
+}
+
+/// 要求配列を 1 行ずつ検証し、送信時のバイト数を返す（直列化した結果は保持しない）
+fn validate_batch_requests(requests: &[serde_json::Value], request_id: &Uuid) -> Result<u64, String> {
+    let mut seen_ids = HashSet::new();
+    let mut total_bytes = 0u64;
+    for (index, value) in requests.iter().enumerate() {
+        check_batch_line(value, &format!("requests[{}]", index), &mut seen_ids, request_id)?;
+        let line_len = serde_json::to_vec(value)
+            .map_err(|e| format!("[Request {}] requests[{}]: {}", request_id, index, e))?
+            .len();
+        total_bytes += line_len as u64 + 1;
+    }
+    Ok(total_bytes)
+}
+
+/// .jsonl ファイルを 1 行ずつ読みながら検証し、行数を返す
+async fn validate_batch_file(file_path: &str, request_id: &Uuid) -> Result<usize, String> {
+    use tokio::io::AsyncBufReadExt;
+
+    let file = tokio::fs::File::open(file_path)
+        .await
+        .map_err(|e| format!("[Request {}] Failed to open file {}: {}", request_id, file_path, e))?;
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut seen_ids = HashSet::new();
+    let mut line_number = 0usize;
+    let mut request_count = 0usize;
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("[Request {}] Failed to read file {}: {}", request_id, file_path, e))?
+    {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let label = format!("{} 行目", line_number);
+        let value: serde_json::Value = serde_json::from_str(&line)
+            .map_err(|e| format!("[Request {}] {}: JSON として解析できません: {}", request_id, label, e))?;
+        check_batch_line(&value, &label, &mut seen_ids, request_id)?;
+        request_count += 1;
+    }
+    Ok(request_count)
+}
+
+/// Batch API の入力ファイル（JSONL）を purpose=batch でアップロードする
+///
+/// 全体をメモリ上に組み立てず、1 行ずつ直列化（またはファイルから読み出し）しながら送信する。
+pub async fn upload_batch_input(request: BatchInputUploadRequest) -> Result<OpenAIResponse, String> {
+    let request_id = Uuid::new_v4();
+    enforce_feature(
+        RestrictedFeature::FileUpload,
+        &secure_config::active_feature_restrictions(),
+        &mask_api_key(&request.api_key),
+        "upload_batch_input",
+        &request_id,
+    )?;
+    run_cancellable(request_id, send_batch_input_upload(request, request_id)).await
+}
+
+async fn send_batch_input_upload(request: BatchInputUploadRequest, request_id: Uuid) -> Result<OpenAIResponse, String> {
+    let start_time = Instant::now();
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let url = build_request_url(&request.base_url, "files", allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let (body, total_bytes, request_count) = match (request.requests, &request.file_path) {
+        (Some(requests), _) => {
+            let total_bytes = validate_batch_requests(&requests, &request_id)?;
+            let request_count = requests.len();
+            let lines = futures_util::stream::iter(requests.into_iter().map(|value| {
+                let mut line = serde_json::to_vec(&value).map_err(std::io::Error::other)?;
+                line.push(b'\n');
+                Ok::<Vec<u8>, std::io::Error>(line)
+            }));
+            (Body::wrap_stream(lines), total_bytes, request_count)
+        }
+        (None, Some(file_path)) => {
+            let request_count = validate_batch_file(file_path, &request_id).await?;
+            let file = tokio::fs::File::open(file_path)
+                .await
+                .map_err(|e| format!("[Request {}] Failed to open file {}: {}", request_id, file_path, e))?;
+            let total_bytes = file
+                .metadata()
+                .await
+                .map_err(|e| format!("[Request {}] Failed to read file metadata: {}", request_id, e))?
+                .len();
+            (Body::from(file), total_bytes, request_count)
+        }
+        (None, None) => {
+            return Err(format!("[Request {}] Neither requests nor file_path was provided", request_id));
+        }
+    };
+
+    if request_count == 0 {
+        return Err(format!("[Request {}] Batch input has no requests", request_id));
+    }
+    if request_count > MAX_BATCH_INPUT_LINES {
+        return Err(format!(
+            "[Request {}] Batch input has {} requests (limit: {})",
+            request_id, request_count, MAX_BATCH_INPUT_LINES
+        ));
+    }
+    if total_bytes > DEFAULT_MAX_UPLOAD_BYTES {
+        return Err(format!(
+            "[Request {}] Batch input is too large: {} bytes (limit: {} bytes)",
+            request_id, total_bytes, DEFAULT_MAX_UPLOAD_BYTES
+        ));
+    }
+
+    log::info!(
+        "[Request {}] Uploading batch input: {} requests, {} bytes",
+        request_id, request_count, total_bytes
+    );
+
+    let client = cached_client(&ClientConfig::new(&request.proxy_config, request.timeout_ms), &request_id)?;
+    let file_name = request
+        .file_name
+        .clone()
+        .unwrap_or_else(|| "batch_input.jsonl".to_string());
+    let file_part = multipart::Part::stream_with_length(body, total_bytes)
+        .file_name(file_name)
+        .mime_str("application/jsonl")
+        .map_err(|e| format!("[Request {}] Failed to create file part: {}", request_id, e))?;
+    let form = multipart::Form::new()
+        .part("file", file_part)
+        .text("purpose", "batch");
+
+    let req_builder = apply_upload_headers(
+        client.post(&url).multipart(form),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    let result = send_upload_request(req_builder, &request_id, start_time).await;
+    metrics::record_request(&result, total_bytes, start_time.elapsed());
+    result
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranscriptionRequest {
+    pub base_url: String,
+    pub api_key: String,
+    pub file_data: String, // Base64 encoded audio data
+    pub file_name: String,
+    pub model: String,
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+    pub response_format: Option<String>,
+    pub additional_headers: Option<HashMap<String, String>>,
+    pub proxy_config: Option<ProxyConfig>,
+    pub allow_insecure_http: Option<bool>,
+    pub timeout_ms: Option<u64>,
+}
+
+/// ファイル名の拡張子から音声の MIME タイプを推定する
+fn audio_mime_type(file_name: &str) -> &'static str {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "mp3" | "mpga" | "mpeg" => "audio/mpeg",
+        "mp4" | "m4a" => "audio/mp4",
+        "wav" => "audio/wav",
+        "webm" => "audio/webm",
+        "ogg" | "oga" => "audio/ogg",
+        "flac" => "audio/flac",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 音声ファイルを文字起こしする（POST /audio/transcriptions）
+pub async fn transcribe_audio(request: TranscriptionRequest) -> Result<OpenAIResponse, String> {
+    let request_id = Uuid::new_v4();
+    let start_time = Instant::now();
+    let bytes_sent = estimated_decoded_size(strip_data_uri_prefix(&request.file_data));
+
+    let result = run_cancellable(request_id, send_transcription(request, request_id)).await;
+    metrics::record_request(&result, bytes_sent, start_time.elapsed());
+    result
+}
+
+async fn send_transcription(request: TranscriptionRequest, request_id: Uuid) -> Result<OpenAIResponse, String> {
+    let start_time = Instant::now();
+
+    log::info!(
+        "[Request {}] Starting transcription: {} (model: {})",
+        request_id, request.file_name, request.model
+    );
+
+    let allow_insecure_http = request.allow_insecure_http.unwrap_or(false);
+    let url = build_request_url(&request.base_url, "audio/transcriptions", allow_insecure_http).map_err(|err| {
+        log::error!("[Request {}] URL validation failed: {}", request_id, err);
+        err
+    })?;
+
+    let client = cached_client(&ClientConfig::new(&request.proxy_config, request.timeout_ms), &request_id)?;
+
+    let file_bytes = decode_base64_file(&request.file_data, &request.file_name, DEFAULT_MAX_UPLOAD_BYTES)
+        .map_err(|err| format!("[Request {}] {}", request_id, err))?;
+
+    let mime_type = audio_mime_type(&request.file_name);
+    log::info!(
+        "[Request {}] Audio size: {} bytes | MIME: {}",
+        request_id, file_bytes.len(), mime_type
+    );
+
+    let file_part = multipart::Part::bytes(file_bytes)
+        .file_name(request.file_name.clone())
+        .mime_str(mime_type)
+        .map_err(|e| format!("[Request {}] Failed to create file part: {}", request_id, e))?;
+
+    let mut form = multipart::Form::new()
+        .part("file", file_part)
+        .text("model", request.model.clone());
+    if let Some(language) = &request.language {
+        form = form.text("language", language.clone());
+    }
+    if let Some(prompt) = &request.prompt {
+        form = form.text("prompt", prompt.clone());
+    }
+    if let Some(response_format) = &request.response_format {
+        form = form.text("response_format", response_format.clone());
+    }
+
+    let req_builder = apply_upload_headers(
+        client.post(&url).multipart(form),
+        &request.additional_headers,
+        &request.api_key,
+        &request_id,
+    );
+
+    send_upload_request(req_builder, &request_id, start_time).await
+}
+
+const DEFAULT_BATCH_POLL_INTERVAL_MS: u64 = 5_000;
+const MIN_BATCH_POLL_INTERVAL_MS: u64 = 1_000;
+const MAX_BATCH_POLL_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_BATCH_POLL_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000;
+const BATCH_TERMINAL_STATUSES: &[&str] = &["completed", "failed", "cancelled", "expired"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchProgress {
+    pub batch_id: String,
+    pub status: String,
+    pub request_counts: Option<serde_json::Value>,
+    pub poll_count: u32,
+}
+
+/// `/batches/{id}` を終了状態（completed / failed / cancelled / expired）になるまでポーリングする
+///
+/// 429・5xx・接続やタイムアウトなど一時的な転送エラーの場合は間隔を倍にして再試行し、
+/// それ以外のエラー（URL・許可ホスト・機能制限・プロキシ設定の誤りなど）はすぐに返す。
+/// ポーリング全体は `group_id`（未指定時は `batch_poll:{batch_id}`）で cancel_request_group から中断できる。
+#[allow(clippy::too_many_arguments)]
+pub async fn poll_batch_status<F>(
+    base_url: String,
+    api_key: String,
+    batch_id: String,
+    interval_ms: Option<u64>,
+    timeout_ms: Option<u64>,
+    proxy_config: Option<ProxyConfig>,
+    group_id: Option<String>,
+    on_progress: F,
+) -> Result<OpenAIResponse, String>
+where
+    F: Fn(BatchProgress),
+{
+    let batch_id = batch_id.trim().to_string();
+    let valid = !batch_id.is_empty()
+        && batch_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(format!("バッチIDが正しくありません: {}", batch_id));
+    }
+
+    let group_id = group_id
+        .map(|group_id| group_id.trim().to_string())
+        .filter(|group_id| !group_id.is_empty())
+        .unwrap_or_else(|| format!("batch_poll:{}", batch_id));
+    let interval = Duration::from_millis(
+        interval_ms
+            .unwrap_or(DEFAULT_BATCH_POLL_INTERVAL_MS)
+            .clamp(MIN_BATCH_POLL_INTERVAL_MS, MAX_BATCH_POLL_INTERVAL_MS),
+    );
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_BATCH_POLL_TIMEOUT_MS));
+    let poll = BatchPoll {
+        base_url,
+        api_key,
+        batch_id,
+        proxy_config,
+        group_id: group_id.clone(),
+        interval,
+        timeout,
+    };
+    run_cancellable_in_group(Uuid::new_v4(), Some(group_id), poll_batch_until_done(poll, on_progress)).await
+}
+
+struct BatchPoll {
+    base_url: String,
+    api_key: String,
+    batch_id: String,
+    proxy_config: Option<ProxyConfig>,
+    group_id: String,
+    interval: Duration,
+    timeout: Duration,
+}
+
+async fn poll_batch_until_done<F>(poll: BatchPoll, on_progress: F) -> Result<OpenAIResponse, String>
+where
+    F: Fn(BatchProgress),
+{
+    let BatchPoll {
+        base_url,
+        api_key,
+        batch_id,
+        proxy_config,
+        group_id,
+        interval,
+        timeout,
+    } = poll;
+    let start_time = Instant::now();
+    let mut delay = interval;
+    let mut poll_count = 0u32;
+
+    log::info!("[Batch {}] Polling status every {:?} (timeout {:?})", batch_id, interval, timeout);
+
+    loop {
+        poll_count += 1;
+        let request = OpenAIRequest {
+            base_url: base_url.clone(),
+            api_key: api_key.clone(),
+            method: "GET".to_string(),
+            path: format!("batches/{}", batch_id),
+            proxy_config: proxy_config.clone(),
+            group_id: Some(group_id.clone()),
+            ..OpenAIRequest::default()
+        };
+        let result = tracked_openai_request(request, &|_| {}).await;
+
+        match result {
+            Ok(response) if (200..300).contains(&response.status) => {
+                let batch: serde_json::Value = serde_json::from_str(&response.body).map_err(|e| {
+                    format!("[Batch {}] Failed to parse batch object: {}", batch_id, e)
+                })?;
+                let status = batch
+                    .get("status")
+                    .and_then(|status| status.as_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+
+                on_progress(BatchProgress {
+                    batch_id: batch_id.clone(),
+                    status: status.clone(),
+                    request_counts: batch.get("request_counts").cloned(),
+                    poll_count,
+                });
+
+                if BATCH_TERMINAL_STATUSES.contains(&status.as_str()) {
+                    log::info!(
+                        "[Batch {}] Reached terminal status {} after {} poll(s) ({:?})",
+                        batch_id, status, poll_count, start_time.elapsed()
+                    );
+                    return Ok(response);
+                }
+                delay = interval;
+            }
+            Ok(response) if response.status == 429 || response.status >= 500 => {
+                delay = (delay * 2).min(Duration::from_millis(MAX_BATCH_POLL_INTERVAL_MS));
+                log::warn!(
+                    "[Batch {}] Poll returned {}, backing off for {:?}",
+                    batch_id, response.status, delay
+                );
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if err.transient => {
+                delay = (delay * 2).min(Duration::from_millis(MAX_BATCH_POLL_INTERVAL_MS));
+                log::warn!("[Batch {}] Poll failed, backing off for {:?}: {}", batch_id, delay, err.message);
+            }
+            Err(err) => {
+                log::error!("[Batch {}] Poll failed: {}", batch_id, err.message);
+                return Err(err.message);
+            }
+        }
+
+        if start_time.elapsed() + delay > timeout {
+            let err_msg = format!(
+                "[Batch {}] Polling timed out after {:?} ({} poll(s))",
+                batch_id, start_time.elapsed(), poll_count
+            );
+            log::error!("{}", err_msg);
+            return Err(err_msg);
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_hosts_must_be_allowlisted_but_may_use_http() {
+        let url = |raw: &str| Url::parse(raw).unwrap();
+        assert!(validate_target_url(&url("http://127.0.0.1:8080/v1"), false).is_ok());
+        assert!(validate_target_url(&url("http://localhost:8080/v1"), false)
+            .unwrap_err()
+            .contains("許可されていません"));
+        assert!(validate_target_url(&url("http://[::1]:8080/v1"), true)
+            .unwrap_err()
+            .contains("許可されていません"));
+        assert!(validate_target_url(&url("http://api.openai.com/v1"), false)
+            .unwrap_err()
+            .contains("HTTPS"));
+    }
+
+    #[test]
+    fn requested_features_detects_tools_paths_and_attachments() {
+        let request = OpenAIRequest {
+            method: "POST".to_string(),
+            path: "/responses".to_string(),
+            body: Some(serde_json::json!({
+                "tools": [{ "type": "web_search_preview" }, { "type": "file_search", "vector_store_ids": ["vs_1"] }],
+                "input": [{ "role": "user", "content": [{ "type": "input_file", "file_id": "file-1" }] }],
+            })),
+            ..OpenAIRequest::default()
+        };
+        assert_eq!(
+            requested_features(&request),
+            vec![
+                RestrictedFeature::WebSearch,
+                RestrictedFeature::VectorStore,
+                RestrictedFeature::ChatFileAttachment
+            ]
+        );
+
+        let vector_store = OpenAIRequest {
+            method: "GET".to_string(),
+            path: "/vector_stores?limit=10".to_string(),
+            ..OpenAIRequest::default()
+        };
+        assert_eq!(requested_features(&vector_store), vec![RestrictedFeature::VectorStore]);
+
+        let plain_chat = OpenAIRequest {
+            raw_body: Some(r#"{"messages":[{"role":"user","content":"hi"}]}"#.to_string()),
+            ..OpenAIRequest::default()
+        };
+        assert!(requested_features(&plain_chat).is_empty());
+    }
+
+    #[test]
+    fn key_identifier_uses_profile_name_for_api_key_refs() {
+        let direct = OpenAIRequest {
+            api_key: "sk-proj-1234567890abcdef".to_string(),
+            ..OpenAIRequest::default()
+        };
+        assert_eq!(request_key_identifier(&direct), "sk-p...cdef");
+
+        let by_ref = OpenAIRequest {
+            api_key_ref: Some(" work ".to_string()),
+            ..OpenAIRequest::default()
+        };
+        assert_eq!(request_key_identifier(&by_ref), "profile:work");
+    }
+
+    #[test]
+    fn transport_retry_requires_idempotent_method_or_key() {
+        let request = |method: &str, idempotency_key: Option<&str>| OpenAIRequest {
+            method: method.to_string(),
+            idempotency_key: idempotency_key.map(str::to_string),
+            ..OpenAIRequest::default()
+        };
+        assert!(is_transport_retry_safe(&request("GET", None)));
+        assert!(is_transport_retry_safe(&request("delete", None)));
+        assert!(!is_transport_retry_safe(&request("POST", None)));
+        assert!(!is_transport_retry_safe(&request("PATCH", Some("  "))));
+        assert!(is_transport_retry_safe(&request("POST", Some("key-1"))));
+    }
+
+    #[test]
+    fn requested_features_treats_file_and_upload_writes_as_file_upload() {
+        let post = |path: &str, raw_url: Option<&str>| OpenAIRequest {
+            method: "post".to_string(),
+            path: path.to_string(),
+            raw_url: raw_url.map(str::to_string),
+            ..OpenAIRequest::default()
+        };
+        assert_eq!(requested_features(&post("files", None)), vec![RestrictedFeature::FileUpload]);
+        assert_eq!(requested_features(&post("/uploads", None)), vec![RestrictedFeature::FileUpload]);
+        assert_eq!(
+            requested_features(&post("/uploads/upload_1/parts?x=1", None)),
+            vec![RestrictedFeature::FileUpload]
+        );
+        assert_eq!(
+            requested_features(&post("https://api.openai.com/v1/files", None)),
+            vec![RestrictedFeature::FileUpload]
+        );
+        assert_eq!(
+            requested_features(&post("/models", Some("https://api.openai.com/v1/uploads/upload_1/complete"))),
+            vec![RestrictedFeature::FileUpload]
+        );
+
+        // 一覧・取得はアップロードではない
+        let list = OpenAIRequest {
+            method: "GET".to_string(),
+            ..post("/files", None)
+        };
+        assert!(requested_features(&list).is_empty());
+    }
+
+    fn file_upload_blocked() -> SecureFeatureRestrictions {
+        SecureFeatureRestrictions {
+            allow_file_upload: Some(false),
+            ..SecureFeatureRestrictions::default()
+        }
+    }
+
+    #[test]
+    fn blocked_file_upload_rejects_generic_upload_requests() {
+        let request = OpenAIRequest {
+            method: "POST".to_string(),
+            path: "uploads".to_string(),
+            api_key: "sk-test-1234567890".to_string(),
+            ..OpenAIRequest::default()
+        };
+        let request_id = Uuid::new_v4();
+        let err = enforce_request_features(&request, &file_upload_blocked(), &request_id).unwrap_err();
+        assert!(err.contains("ファイルのアップロード"), "{}", err);
+        assert!(enforce_request_features(&request, &SecureFeatureRestrictions::default(), &request_id).is_ok());
+    }
+
+    #[test]
+    fn blocked_file_upload_rejects_upload_operations() {
+        let request_id = Uuid::new_v4();
+        for operation in ["upload_file", "create_upload", "upload_part", "complete_upload", "upload_files", "upload_batch_input"] {
+            let err = enforce_feature(RestrictedFeature::FileUpload, &file_upload_blocked(), "sk-t...7890", operation, &request_id)
+                .unwrap_err();
+            assert!(err.contains("ファイルのアップロード"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn unspecified_restrictions_allow_features() {
+        let restrictions = SecureFeatureRestrictions {
+            allow_web_search: Some(false),
+            ..SecureFeatureRestrictions::default()
+        };
+        assert!(!RestrictedFeature::WebSearch.is_allowed(&restrictions));
+        assert!(RestrictedFeature::FileUpload.is_allowed(&restrictions));
+    }
+
+    #[tokio::test]
+    async fn host_resolution_reports_hostname_and_addresses() {
+        let resolution = describe_host_resolution("http://localhost:8080/v1/models").await;
+        assert!(resolution.starts_with("Host localhost resolved to "), "{}", resolution);
+        assert!(resolution.contains("127.0.0.1") || resolution.contains("::1"), "{}", resolution);
+    }
+
+    #[test]
+    fn response_redaction_removes_or_masks_pointed_values() {
+        let body = r#"{"id":"resp_1","choices":[{"message":{"content":"hi","a/b":"x"}},{"message":{"content":"yo"}}],"usage":{"total_tokens":3}}"#;
+        let request_id = Uuid::new_v4();
+
+        let remove = ResponseRedactionConfig {
+            pointers: vec!["/choices/*/message/content".to_string(), "/choices/0/message/a~1b".to_string()],
+            mode: RedactionMode::Remove,
+        };
+        let removed: serde_json::Value =
+            serde_json::from_str(&redact_response_body(body.to_string(), &remove, &request_id)).unwrap();
+        assert_eq!(removed["choices"][0]["message"], serde_json::json!({}));
+        assert_eq!(removed["choices"][1]["message"], serde_json::json!({}));
+        assert_eq!(removed["usage"]["total_tokens"], 3);
+
+        let mask = ResponseRedactionConfig {
+            pointers: vec!["/id".to_string()],
+            mode: RedactionMode::Mask,
+        };
+        let masked: serde_json::Value =
+            serde_json::from_str(&redact_response_body(body.to_string(), &mask, &request_id)).unwrap();
+        assert_eq!(masked["id"], REDACTED_VALUE);
+
+        // JSON 以外はそのまま返す
+        assert_eq!(redact_response_body("not json".to_string(), &mask, &request_id), "not json");
+    }
+
+    #[test]
+    fn strip_data_uri_prefix_handles_prefixes_and_multibyte_input() {
+        assert_eq!(strip_data_uri_prefix(" data:text/plain;base64,aGk= "), "aGk=");
+        assert_eq!(strip_data_uri_prefix("DATA:application/json;base64,e30="), "e30=");
+        assert_eq!(strip_data_uri_prefix("aGk="), "aGk=");
+        // 5 バイト目がマルチバイト文字の途中でも panic しない
+        assert_eq!(strip_data_uri_prefix("データ"), "データ");
+        assert_eq!(strip_data_uri_prefix("dat"), "dat");
+    }
+
+    #[test]
+    fn estimated_decoded_size_accounts_for_padding() {
+        assert_eq!(estimated_decoded_size("aGk="), 2);
+        assert_eq!(estimated_decoded_size("aGk"), 2);
+        assert_eq!(estimated_decoded_size("aGVsbG8="), 5);
+        assert_eq!(estimated_decoded_size("aGVsbG8h"), 6);
+        assert_eq!(estimated_decoded_size(""), 0);
+        // 不正な入力（非 ASCII）でもバイト数から見積もるだけで panic しない
+        assert_eq!(estimated_decoded_size("データ"), 6);
+    }
+
+    #[test]
+    fn mask_api_key_hides_short_keys_entirely() {
+        assert_eq!(mask_api_key(""), "****");
+        assert_eq!(mask_api_key("sk-abc"), "****");
+        assert_eq!(mask_api_key("12345678"), "****");
+    }
+
+    #[test]
+    fn mask_api_key_keeps_head_and_tail_of_long_keys() {
+        assert_eq!(mask_api_key("sk-proj-1234567890abcdef"), "sk-p...cdef");
+        assert_eq!(mask_api_key("123456789"), "1234...6789");
+        // マルチバイト文字を含んでも panic しない
+        assert_eq!(mask_api_key("キーキーキーキーキー"), "キーキー...キーキー");
+    }
+
+    #[test]
+    fn truncate_for_log_respects_multibyte_boundaries() {
+        // 1000 バイト目が「あ」（3 バイト）の途中にあたるため、バイト単位で切ると panic する
+        let body = "あ".repeat(600);
+        let (preview, truncated) = truncate_for_log(&body, 1000);
+        assert!(!truncated);
+        assert_eq!(preview, body);
+
+        let (preview, truncated) = truncate_for_log(&body, 500);
+        assert!(truncated);
+        assert_eq!(preview.chars().count(), 500);
+        assert_eq!(preview, "あ".repeat(500));
+    }
+
+    #[test]
+    fn truncate_for_log_keeps_short_text() {
+        assert_eq!(truncate_for_log("short", 10), ("short", false));
+        assert_eq!(truncate_for_log("", 10), ("", false));
+    }
+
+    #[test]
+    fn build_request_url_joins_base_and_path() {
+        let expected = "https://api.openai.com/v1/models";
+        assert_eq!(build_request_url("https://api.openai.com/v1", "/models", false).unwrap(), expected);
+        assert_eq!(build_request_url("https://api.openai.com/v1/", "models", false).unwrap(), expected);
+        assert_eq!(build_request_url(" https://api.openai.com/v1// ", "//models", false).unwrap(), expected);
+    }
+
+    #[test]
+    fn build_request_url_uses_absolute_path_verbatim() {
+        let absolute = "https://api.openai.com/v1/batches/batch_123?signature=abc";
+        assert_eq!(build_request_url("https://api.openai.com/v1", absolute, false).unwrap(), absolute);
+    }
+
+    #[test]
+    fn apply_query_params_encodes_and_overrides_inline_query() {
+        let query = Some(HashMap::from([
+            ("after".to_string(), "file_2".to_string()),
+            ("q".to_string(), "日本語 & space".to_string()),
+        ]));
+        let url = apply_query_params("https://api.openai.com/v1/files?after=file_1&limit=10", &query).unwrap();
+        assert_eq!(
+            url,
+            "https://api.openai.com/v1/files?limit=10&after=file_2&q=%E6%97%A5%E6%9C%AC%E8%AA%9E+%26+space"
+        );
+
+        // query がなければ URL を書き換えない
+        let inline = "https://api.openai.com/v1/files?limit=10";
+        assert_eq!(apply_query_params(inline, &None).unwrap(), inline);
+    }
+
+    #[test]
+    fn build_request_url_validates_absolute_path_host() {
+        assert!(build_request_url("https://api.openai.com/v1", "https://evil.example.com/steal", false).is_err());
+        assert!(build_request_url("https://api.openai.com/v1", "http://api.openai.com/v1/models", false).is_err());
+    }
+
+    fn proxied_config(timeout_ms: Option<u64>) -> ClientConfig {
+        let proxy_config = Some(ProxyConfig {
+            http_proxy: Some("http://proxy.example.com:8080".to_string()),
+            https_proxy: Some("http://proxy.example.com:8080".to_string()),
+        });
+        ClientConfig::new(&proxy_config, timeout_ms)
+    }
+
+    #[test]
+    fn client_config_equal_for_equivalent_settings() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |config: &ClientConfig| {
+            let mut hasher = DefaultHasher::new();
+            config.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let first = proxied_config(Some(30_000));
+        let second = proxied_config(Some(30_000));
+        assert_eq!(first, second);
+        assert_eq!(hash(&first), hash(&second));
+        assert_ne!(first, proxied_config(Some(60_000)));
+
+        // 接続プールの設定が違えば別のクライアントとしてキャッシュする
+        let pooled = ClientConfig {
+            pool_max_idle_per_host: Some(2),
+            ..proxied_config(Some(30_000))
+        };
+        assert_ne!(first, pooled);
+        assert_ne!(hash(&first), hash(&pooled));
+    }
+
+    #[test]
+    fn build_client_builds_identical_clients_for_equal_configs() {
+        let request_id = Uuid::new_v4();
+        let first = build_client(&proxied_config(Some(30_000)), &request_id).unwrap();
+        let second = build_client(&proxied_config(Some(30_000)), &request_id).unwrap();
+        let different = build_client(&proxied_config(Some(60_000)), &request_id).unwrap();
+
+        // reqwest::Client は比較できないため、設定内容を含む Debug 表現で比較する
+        assert_eq!(format!("{:?}", first), format!("{:?}", second));
+        assert_ne!(format!("{:?}", first), format!("{:?}", different));
+    }
+
+    #[test]
+    fn build_client_rejects_unknown_tls_version() {
+        let config = ClientConfig {
+            min_tls_version: Some("2.0".to_string()),
+            ..ClientConfig::default()
+        };
+        assert!(build_client(&config, &Uuid::new_v4()).is_err());
+    }
+}
+
+/// ローカルのモックサーバーに対してリクエスト処理全体を確認する
+#[cfg(test)]
+mod mock_server_tests {
+    use super::*;
+    use std::sync::Mutex;
+    use wiremock::matchers::{body_json, header, header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const TEST_API_KEY: &str = "sk-test-1234567890";
+
+    fn mock_request(server: &MockServer, method: &str, path: &str) -> OpenAIRequest {
+        OpenAIRequest {
+            base_url: format!("{}/v1", server.uri()),
+            api_key: TEST_API_KEY.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            // 環境変数のプロキシを経由させない
+            proxy_config: Some(ProxyConfig::default()),
+            ..OpenAIRequest::default()
+        }
+    }
+
+    fn mock_upload_request(server: &MockServer, purpose: &str) -> FileUploadRequest {
+        FileUploadRequest {
+            base_url: format!("{}/v1", server.uri()),
+            api_key: TEST_API_KEY.to_string(),
+            file_data: general_purpose::STANDARD.encode(b"{\"custom_id\":\"1\"}\n"),
+            file_name: "input.jsonl".to_string(),
+            purpose: purpose.to_string(),
+            additional_headers: None,
+            proxy_config: Some(ProxyConfig::default()),
+            allow_insecure_http: None,
+            max_upload_bytes: None,
+            connect_timeout_ms: None,
+            upload_timeout_ms: None,
+            use_system_certs: None,
+            allow_unknown_purpose: None,
+            gzip_before_upload: None,
+            min_tls_version: None,
+        }
+    }
+
+    async fn direct_get(url: &str) -> reqwest::Response {
+        Client::builder().no_proxy().build().unwrap().get(url).send().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_request_returns_body_and_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", format!("Bearer {}", TEST_API_KEY).as_str()))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"data":[]}"#)
+                    .insert_header("x-request-id", "req_123"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = mock_request(&server, "GET", "/models");
+        let base_url = request.base_url.clone();
+        let response = make_openai_request(request).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, r#"{"data":[]}"#);
+        assert_eq!(response.headers.get("x-request-id").map(String::as_str), Some("req_123"));
+        assert!(response.remote_addr.as_deref().is_some_and(|addr| addr.starts_with("127.0.0.1:")));
+        assert_eq!(response.served_by, Some(base_url));
+        assert_eq!(response.attempts, 1);
+        assert!(response.error_details.is_none());
+    }
+
+    #[tokio::test]
+    async fn post_request_sends_json_body() {
+        let server = MockServer::start().await;
+        let body = serde_json::json!({ "model": "gpt-4o-mini", "input": "こんにちは" });
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .and(header("content-type", "application/json"))
+            .and(body_json(&body))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":"resp_1"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = OpenAIRequest {
+            body: Some(body),
+            ..mock_request(&server, "POST", "/responses")
+        };
+        let response = make_openai_request(request).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, r#"{"id":"resp_1"}"#);
+    }
+
+    #[tokio::test]
+    async fn raw_url_is_used_without_trimming() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = OpenAIRequest {
+            raw_url: Some(format!("{}/v1/models/", server.uri())),
+            ..mock_request(&server, "GET", "/ignored")
+        };
+        let response = make_openai_request(request).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert!(response.served_by.is_none());
+    }
+
+    #[tokio::test]
+    async fn provider_profile_defaults_are_applied_unless_overridden() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt/chat/completions"))
+            .and(wiremock::matchers::query_param("api-version", "2024-06-01"))
+            .and(header("api-key", TEST_API_KEY))
+            .and(header("x-gateway", "request"))
+            .and(header("x-tenant", "kiosk"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        register_provider_profile(
+            "azure-test",
+            ProviderProfile {
+                base_url: Some(server.uri()),
+                auth_header: Some("api-key".to_string()),
+                headers: Some(HashMap::from([
+                    ("X-Gateway".to_string(), "profile".to_string()),
+                    ("x-tenant".to_string(), "kiosk".to_string()),
+                ])),
+                api_version: Some("2024-06-01".to_string()),
+            },
+        )
+        .unwrap();
+
+        let request = OpenAIRequest {
+            base_url: String::new(),
+            provider_profile: Some("azure-test".to_string()),
+            additional_headers: Some(HashMap::from([("x-gateway".to_string(), "request".to_string())])),
+            body: Some(serde_json::json!({ "messages": [] })),
+            ..mock_request(&server, "POST", "/openai/deployments/gpt/chat/completions")
+        };
+        let response = make_openai_request(request).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests[0].headers.get("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn unknown_provider_profile_is_rejected() {
+        let request = OpenAIRequest {
+            base_url: "https://api.openai.com/v1".to_string(),
+            provider_profile: Some("missing".to_string()),
+            ..OpenAIRequest::default()
+        };
+        let err = make_openai_request(request).await.unwrap_err();
+        assert!(err.contains("missing"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn http2_prior_knowledge_rejects_plaintext_urls() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let request = OpenAIRequest {
+            http2_prior_knowledge: Some(true),
+            ..mock_request(&server, "GET", "/models")
+        };
+        let err = make_openai_request(request).await.unwrap_err();
+        assert!(err.contains("http2_prior_knowledge"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn min_tls_version_is_applied_to_requests_and_upload_parts() {
+        let server = MockServer::start().await;
+        mount_unreachable(&server).await;
+
+        let request = OpenAIRequest {
+            min_tls_version: Some("2.0".to_string()),
+            ..mock_request(&server, "GET", "/models")
+        };
+        let err = make_openai_request(request).await.unwrap_err();
+        assert!(err.contains("TLS バージョン"), "{}", err);
+
+        let file_path = std::env::temp_dir().join(format!("upload-part-tls-{}.bin", Uuid::new_v4()));
+        std::fs::write(&file_path, b"data").unwrap();
+        let request = UploadPartRequest {
+            base_url: format!("{}/v1", server.uri()),
+            api_key: TEST_API_KEY.to_string(),
+            upload_id: "upload_1".to_string(),
+            file_path: file_path.display().to_string(),
+            offset: 0,
+            length: 4,
+            additional_headers: None,
+            proxy_config: Some(ProxyConfig::default()),
+            allow_insecure_http: None,
+            timeout_ms: None,
+            min_tls_version: Some("2.0".to_string()),
+        };
+        let err = upload_part(request, |_| {}).await.unwrap_err();
+        let _ = std::fs::remove_file(&file_path);
+        assert!(err.contains("TLS バージョン"), "{}", err);
+    }
+
+    /// テスト中に出力された warn 以上のログを保持する（ロガーはプロセスで 1 つのため全テストで共有する）
+    static CAPTURED_LOGS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+    struct CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                if let Ok(mut logs) = CAPTURED_LOGS.lock() {
+                    logs.push(record.args().to_string());
+                }
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn capture_logs() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&CapturingLogger).expect("logger already set");
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+    }
+
+    async fn mount_unreachable(server: &MockServer) {
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":"upload_1"}"#))
+            .expect(0)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn oversized_local_file_is_rejected_before_reading() {
+        let server = MockServer::start().await;
+        mount_unreachable(&server).await;
+
+        // 疎なファイルにして、実際にはディスクを使わない
+        let file_path = std::env::temp_dir().join(format!("oversized-upload-{}.bin", Uuid::new_v4()));
+        std::fs::File::create(&file_path)
+            .and_then(|file| file.set_len(DEFAULT_MAX_UPLOAD_BYTES + 1))
+            .unwrap();
+
+        let request = MultiFileUploadRequest {
+            base_url: format!("{}/v1", server.uri()),
+            api_key: TEST_API_KEY.to_string(),
+            files: vec![FileEntry {
+                file_data: None,
+                file_path: Some(file_path.display().to_string()),
+                file_name: "large.jsonl".to_string(),
+                mime_type: None,
+            }],
+            purpose: "batch".to_string(),
+            path: None,
+            field_name: None,
+            indexed_field_names: None,
+            additional_headers: None,
+            proxy_config: Some(ProxyConfig::default()),
+            allow_insecure_http: None,
+            timeout_ms: None,
+            allow_unknown_purpose: None,
+            gzip_before_upload: None,
+        };
+        let result = upload_files_to_openai(request).await;
+        let _ = std::fs::remove_file(&file_path);
+
+        assert!(result.unwrap_err().contains("大きすぎます"));
+    }
+
+    #[tokio::test]
+    async fn upload_part_streams_only_the_requested_range() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/uploads/upload_1/parts"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":"part_1"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let file_path = std::env::temp_dir().join(format!("upload-part-test-{}.bin", Uuid::new_v4()));
+        let content: Vec<u8> = (0..(UPLOAD_PROGRESS_CHUNK_SIZE * 3)).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&file_path, &content).unwrap();
+
+        let offset = 10;
+        let length = UPLOAD_PROGRESS_CHUNK_SIZE + 5;
+        let request = UploadPartRequest {
+            base_url: format!("{}/v1", server.uri()),
+            api_key: TEST_API_KEY.to_string(),
+            upload_id: "upload_1".to_string(),
+            file_path: file_path.display().to_string(),
+            offset: offset as u64,
+            length: length as u64,
+            additional_headers: None,
+            proxy_config: Some(ProxyConfig::default()),
+            allow_insecure_http: None,
+            timeout_ms: None,
+            min_tls_version: None,
+        };
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let recorded = progress.clone();
+        let response = upload_part(request, move |update| recorded.lock().unwrap().push(update.bytes_sent)).await;
+        let _ = std::fs::remove_file(&file_path);
+
+        assert_eq!(response.unwrap().status, 200);
+        let requests = server.received_requests().await.unwrap();
+        let expected = &content[offset..offset + length];
+        assert!(requests[0].body.windows(expected.len()).any(|window| window == expected));
+        assert_eq!(progress.lock().unwrap().last().copied(), Some(length as u64));
+    }
+
+    #[tokio::test]
+    async fn stream_uses_request_method_and_emits_final_line_without_newline() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/responses/resp_1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string("data: {\"n\":1}\n\ndata: {\"n\":2}"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let chunks = Mutex::new(Vec::new());
+        let done = stream_openai_request(
+            mock_request(&server, "GET", "/responses/resp_1"),
+            "stream-1".to_string(),
+            |chunk| chunks.lock().unwrap().push(chunk.data),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(done.status, 200);
+        assert_eq!(*chunks.lock().unwrap(), vec![r#"{"n":1}"#.to_string(), r#"{"n":2}"#.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn batch_poll_returns_permanent_errors_without_backing_off() {
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            poll_batch_status(
+                "https://not-allowed.example.com/v1".to_string(),
+                TEST_API_KEY.to_string(),
+                "batch_1".to_string(),
+                None,
+                None,
+                Some(ProxyConfig::default()),
+                None,
+                |_| {},
+            ),
+        )
+        .await
+        .expect("permanent errors must not be retried");
+
+        assert!(result.unwrap_err().contains("許可されていません"));
+    }
+
+    #[tokio::test]
+    async fn batch_poll_can_be_cancelled_by_group() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/batches/batch_1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":"batch_1","status":"in_progress"}"#))
+            .mount(&server)
+            .await;
+
+        let poll = poll_batch_status(
+            format!("{}/v1", server.uri()),
+            TEST_API_KEY.to_string(),
+            "batch_1".to_string(),
+            None,
+            None,
+            Some(ProxyConfig::default()),
+            Some("batch-poll-test".to_string()),
+            |_| {},
+        );
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            cancel_request_group("batch-poll-test")
+        };
+        let (result, cancelled) = tokio::join!(poll, cancel);
+
+        assert!(cancelled >= 1);
+        assert!(result.unwrap_err().ends_with("Request cancelled"));
+    }
+
+    #[tokio::test]
+    async fn stream_sends_raw_body_content_type_and_idempotency_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .and(header("content-type", "application/x-ndjson"))
+            .and(header("idempotency-key", "key-1"))
+            .and(header("accept", "text/event-stream"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data: {}\n\ndata: [DONE]\n\n"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = OpenAIRequest {
+            raw_body: Some("{\"a\":1}\n".to_string()),
+            content_type: Some("application/x-ndjson".to_string()),
+            idempotency_key: Some("key-1".to_string()),
+            ..mock_request(&server, "POST", "/responses")
+        };
+        let done = stream_openai_request(request, "stream-raw".to_string(), |_| {}).await.unwrap();
+
+        assert_eq!(done.status, 200);
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].body, b"{\"a\":1}\n");
+    }
+
+    #[tokio::test]
+    async fn stream_respects_overall_deadline() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let request = OpenAIRequest {
+            overall_deadline_ms: Some(100),
+            ..mock_request(&server, "POST", "/responses")
+        };
+        let err = stream_openai_request(request, "stream-deadline".to_string(), |_| {}).await.unwrap_err();
+
+        assert!(err.contains("overall deadline exceeded"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn warmup_connection_succeeds_without_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = warmup_connection(format!("{}/v1", server.uri()), Some(ProxyConfig::default()))
+            .await
+            .unwrap();
+
+        assert_eq!(result.status, 401);
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests[0].headers.get("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn validate_api_key_classifies_responses() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", format!("Bearer {}", TEST_API_KEY).as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"data":[]}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_string(
+                r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let base_url = format!("{}/v1", server.uri());
+        let proxy_config = Some(ProxyConfig::default());
+        let valid = validate_api_key(base_url.clone(), TEST_API_KEY.to_string(), proxy_config.clone())
+            .await
+            .unwrap();
+        assert!(matches!(valid, ApiKeyValidation::Valid { .. }), "{:?}", valid);
+
+        let invalid = validate_api_key(base_url, "sk-wrong-0000000000".to_string(), proxy_config)
+            .await
+            .unwrap();
+        match invalid {
+            ApiKeyValidation::Invalid { auth_error, message } => {
+                assert_eq!(auth_error, AuthError::InvalidCredentials);
+                assert_eq!(message.as_deref(), Some("Incorrect API key provided"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limited_response_is_returned_with_error_details() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(429).set_body_string(
+                r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#,
+            ))
+            // 429 はフォールバック・再送しない
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = make_openai_request(mock_request(&server, "GET", "/models")).await.unwrap();
+
+        assert_eq!(response.status, 429);
+        let details = response.error_details.unwrap();
+        assert_eq!(details.code.as_deref(), Some("rate_limit_exceeded"));
+        assert_eq!(details.message.as_deref(), Some("Rate limit reached"));
+        assert!(response.auth_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn server_error_is_returned_when_no_fallback_is_configured() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(500).set_body_string(
+                r#"{"error":{"message":"Internal error","type":"server_error","code":null}}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = make_openai_request(mock_request(&server, "GET", "/models")).await.unwrap();
+
+        assert_eq!(response.status, 500);
+        assert_eq!(response.error_details.unwrap().error_type.as_deref(), Some("server_error"));
+    }
+
+    #[tokio::test]
+    async fn server_error_falls_back_to_next_base_url() {
+        let primary = MockServer::start().await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"data":[]}"#))
+            .expect(1)
+            .mount(&fallback)
+            .await;
+
+        let fallback_base_url = format!("{}/v1", fallback.uri());
+        let request = OpenAIRequest {
+            fallback_base_urls: Some(vec![fallback_base_url.clone()]),
+            ..mock_request(&primary, "GET", "/models")
+        };
+        let response = make_openai_request(request).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.served_by, Some(fallback_base_url));
+        assert_eq!(response.attempts, 2);
+    }
+
+    // モックサーバーは使い回されるため、キャッシュキーが他のテストと重ならないようクエリで区別する
+    fn cached_request(server: &MockServer, cache_ttl_ms: u64, cache_test_id: &Uuid) -> OpenAIRequest {
+        OpenAIRequest {
+            cache_ttl_ms: Some(cache_ttl_ms),
+            ..mock_request(server, "GET", &format!("/models?cache_test={}", cache_test_id))
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_cache_hit_makes_no_request() {
+        let server = MockServer::start().await;
+        let cache_test_id = Uuid::new_v4();
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"data":["cached"]}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let first = make_openai_request(cached_request(&server, 60_000, &cache_test_id)).await.unwrap();
+        let second = make_openai_request(cached_request(&server, 60_000, &cache_test_id)).await.unwrap();
+
+        assert_eq!(second.body, first.body);
+        assert_eq!(second.attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn stale_cache_entry_is_revalidated_with_if_none_match() {
+        let server = MockServer::start().await;
+        let cache_test_id = Uuid::new_v4();
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"data":["new"]}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_string(r#"{"data":["old"]}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        make_openai_request(cached_request(&server, 1, &cache_test_id)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let response = make_openai_request(cached_request(&server, 1, &cache_test_id)).await.unwrap();
+
+        assert_eq!(response.body, r#"{"data":["new"]}"#);
+    }
+
+    #[tokio::test]
+    async fn not_modified_returns_cached_body_with_status_200() {
+        let server = MockServer::start().await;
+        let cache_test_id = Uuid::new_v4();
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_string(r#"{"data":["cached"]}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        make_openai_request(cached_request(&server, 1, &cache_test_id)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let response = make_openai_request(cached_request(&server, 1, &cache_test_id)).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, r#"{"data":["cached"]}"#);
+        assert_eq!(response.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn oversized_response_does_not_fall_back() {
+        let primary = MockServer::start().await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![b'x'; 4096]))
+            .expect(1)
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&fallback)
+            .await;
+
+        let request = OpenAIRequest {
+            fallback_base_urls: Some(vec![format!("{}/v1", fallback.uri())]),
+            max_response_bytes: Some(1024),
+            ..mock_request(&primary, "GET", "/models")
+        };
+        let err = make_openai_request(request).await.unwrap_err();
+
+        assert!(err.contains("Response too large"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn overall_deadline_includes_fallback_attempts() {
+        let primary = MockServer::start().await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(503).set_delay(Duration::from_millis(300)))
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(300)))
+            .mount(&fallback)
+            .await;
+
+        // 各ベースURLは期限内に応答するが、合計では期限を超える
+        let request = OpenAIRequest {
+            fallback_base_urls: Some(vec![format!("{}/v1", fallback.uri())]),
+            overall_deadline_ms: Some(500),
+            ..mock_request(&primary, "GET", "/models")
+        };
+        let err = make_openai_request(request).await.unwrap_err();
+
+        assert!(err.contains("overall deadline exceeded"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn oversized_response_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/large"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![b'x'; 4096]))
+            .mount(&server)
+            .await;
+
+        let response = direct_get(&format!("{}/large", server.uri())).await;
+        let err = read_response_body(response, 1024, None, &|_| {}, &Uuid::new_v4())
+            .await
+            .unwrap_err();
+
+        assert!(err.contains("Response too large"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn dropped_connection_reports_received_and_expected_bytes() {
+        use tokio::io::AsyncWriteExt;
+
+        // Content-Length より少ないバイト数を送った時点で接続を切るサーバー
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = tokio::io::AsyncReadExt::read(&mut socket, &mut buf).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Length: 4096\r\nContent-Type: application/json\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&[b'x'; 1024]).await.unwrap();
+            socket.shutdown().await.unwrap();
+        });
+
+        let response = direct_get(&format!("http://{}/partial", addr)).await;
+        let err = read_response_body(response, DEFAULT_MAX_RESPONSE_BYTES, None, &|_| {}, &Uuid::new_v4())
+            .await
+            .unwrap_err();
+
+        assert!(err.contains("Response body truncated"), "{}", err);
+        assert!(err.contains("received 1.0KB (1024 bytes) of an expected 4.0KB (4096 bytes)"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_utf8_body_is_decoded_lossily() {
+        let body = decode_response_body(vec![b'o', b'k', 0xff], &Uuid::new_v4());
+        assert_eq!(body, "ok\u{FFFD}");
+    }
+
+    #[tokio::test]
+    async fn raising_max_response_bytes_allows_larger_responses() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/files/file-abc/content"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![b'x'; 2048]))
+            .mount(&server)
+            .await;
+
+        let limited = OpenAIRequest {
+            max_response_bytes: Some(1024),
+            ..mock_request(&server, "GET", "/files/file-abc/content")
+        };
+        let err = make_openai_request(limited).await.unwrap_err();
+        assert!(err.contains("Response too large"), "{}", err);
+
+        let raised = OpenAIRequest {
+            max_response_bytes: Some(4096),
+            ..mock_request(&server, "GET", "/files/file-abc/content")
+        };
+        let response = make_openai_request(raised).await.unwrap();
+        assert_eq!(response.body.len(), 2048);
+    }
+
+    #[test]
+    fn large_response_warning_follows_effective_limit() {
+        let limits = LogLimits::default();
+        assert_eq!(effective_max_response_bytes(None), DEFAULT_MAX_RESPONSE_BYTES);
+        assert_eq!(large_response_warning_bytes(&limits, DEFAULT_MAX_RESPONSE_BYTES), 10 * 1024 * 1024);
+
+        let raised = effective_max_response_bytes(Some(200 * 1024 * 1024));
+        assert_eq!(large_response_warning_bytes(&limits, raised), 40 * 1024 * 1024);
+        assert_eq!(effective_max_response_bytes(Some(u64::MAX)), MAX_RESPONSE_BYTES_LIMIT);
+
+        // set_log_limits で明示した値は上限に関わらず優先する
+        let explicit = LogLimits {
+            large_response_warning_bytes: Some(1024),
+            ..LogLimits::default()
+        };
+        assert_eq!(large_response_warning_bytes(&explicit, raised), 1024);
+    }
+
+    #[tokio::test]
+    async fn response_body_reports_progress_until_complete() {
+        let server = MockServer::start().await;
+        let body = vec![b'x'; RESPONSE_PROGRESS_INTERVAL_BYTES * 2 + 10];
+        Mock::given(method("GET"))
+            .and(path("/file"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+            .mount(&server)
+            .await;
+
+        let events = Mutex::new(Vec::new());
+        let response = direct_get(&format!("{}/file", server.uri())).await;
+        let received = read_response_body(
+            response,
+            DEFAULT_MAX_RESPONSE_BYTES,
+            Some("progress-1"),
+            &|progress: ResponseProgress| events.lock().unwrap().push(progress),
+            &Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(received, body);
+        let events = events.into_inner().unwrap();
+        let last = events.last().unwrap();
+        assert_eq!(last.progress_id, "progress-1");
+        assert_eq!(last.bytes_received, body.len() as u64);
+        assert_eq!(last.total_bytes, Some(body.len() as u64));
+    }
+
+    #[tokio::test]
+    async fn invalid_proxy_configuration_fails_before_sending() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let request = OpenAIRequest {
+            proxy_config: Some(ProxyConfig {
+                http_proxy: Some("http://bad proxy:8080".to_string()),
+                https_proxy: None,
+            }),
+            ..mock_request(&server, "GET", "/models")
+        };
+        let err = make_openai_request(request).await.unwrap_err();
+
+        assert!(err.contains("proxy configuration error"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn multibyte_error_body_is_truncated_in_logs() {
+        capture_logs();
+        let server = MockServer::start().await;
+        // 他のテストのログと区別できるよう、先頭に一意な値を付ける
+        let body = format!("{}{}", Uuid::new_v4(), "エラー".repeat(1000));
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(body.clone()))
+            .mount(&server)
+            .await;
+
+        let response = make_openai_request(mock_request(&server, "GET", "/models")).await.unwrap();
+        assert_eq!(response.status, 400);
+        assert_eq!(response.body, body);
+
+        let max_chars = current_log_limits().max_log_body_chars;
+        let expected = format!(
+            "OpenAI API error (400): {}...(truncated, total {} bytes)",
+            body.chars().take(max_chars).collect::<String>(),
+            body.len()
+        );
+        let logs = CAPTURED_LOGS.lock().unwrap();
+        assert!(logs.iter().any(|line| line.ends_with(&expected)), "no truncated error log for {}", &body[..36]);
+    }
+
+    #[tokio::test]
+    async fn file_upload_sends_multipart_form() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/files"))
+            .and(header_regex("content-type", "^multipart/form-data"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":"file-abc"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = upload_file_to_openai(mock_upload_request(&server, "batch")).await.unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, r#"{"id":"file-abc"}"#);
+    }
+
+    #[tokio::test]
+    async fn file_upload_rejects_unknown_purpose_without_sending() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = upload_file_to_openai(mock_upload_request(&server, "assistant")).await.unwrap_err();
+
+        assert!(err.contains("assistants"), "{}", err);
+    }
+}
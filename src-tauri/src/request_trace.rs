@@ -0,0 +1,116 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+
+use crate::openai_proxy::RequestTiming;
+
+// 記録件数の上限（設定値がこれを超えても切り詰める）
+const MAX_TRACE_CAPACITY: usize = 1000;
+
+static TRACE_CONFIG: Lazy<RwLock<RequestTraceConfig>> =
+    Lazy::new(|| RwLock::new(RequestTraceConfig::default()));
+
+/// 直近のリクエストの記録（古いものから捨てるリングバッファ）
+static RECENT_REQUESTS: Lazy<Mutex<VecDeque<RecentRequest>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// 直近のリクエストを記録する設定（既定は無効）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTraceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    // true の場合は切り詰めたリクエスト・レスポンスボディも記録する
+    #[serde(default)]
+    pub include_bodies: bool,
+}
+
+fn default_capacity() -> usize {
+    50
+}
+
+impl Default for RequestTraceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: default_capacity(),
+            include_bodies: false,
+        }
+    }
+}
+
+/// 1 件分のリクエストの記録（ヘッダーの機密値はマスク済み）
+#[derive(Debug, Serialize, Clone)]
+pub struct RecentRequest {
+    pub request_id: String,
+    pub timestamp_ms: u64,
+    pub method: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub body_size: usize,
+    pub status: Option<u16>,
+    pub timing: Option<RequestTiming>,
+    pub remote_addr: Option<String>,
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub request_body: Option<String>,
+    pub response_body: Option<String>,
+}
+
+pub fn current_config() -> RequestTraceConfig {
+    TRACE_CONFIG.read().map(|config| *config).unwrap_or_default()
+}
+
+pub fn record(entry: RecentRequest) {
+    let capacity = current_config().capacity.min(MAX_TRACE_CAPACITY);
+    let mut requests = match RECENT_REQUESTS.lock() {
+        Ok(requests) => requests,
+        Err(_) => return,
+    };
+    requests.push_back(entry);
+    while requests.len() > capacity {
+        requests.pop_front();
+    }
+}
+
+#[tauri::command]
+pub fn get_recent_requests() -> Result<Vec<RecentRequest>, String> {
+    RECENT_REQUESTS
+        .lock()
+        .map(|requests| requests.iter().cloned().collect())
+        .map_err(|_| "リクエスト履歴の取得に失敗しました".to_string())
+}
+
+#[tauri::command]
+pub fn clear_recent_requests() -> Result<usize, String> {
+    let mut requests = RECENT_REQUESTS
+        .lock()
+        .map_err(|_| "リクエスト履歴のクリアに失敗しました".to_string())?;
+    let cleared = requests.len();
+    requests.clear();
+    log::info!("Recent request trace cleared ({} entries)", cleared);
+    Ok(cleared)
+}
+
+/// 記録の有効化・件数・ボディを含めるかを変更する（無効化すると記録済みの内容も破棄する）
+#[tauri::command]
+pub fn set_request_trace_config(config: RequestTraceConfig) -> Result<(), String> {
+    let mut current = TRACE_CONFIG
+        .write()
+        .map_err(|_| "リクエスト履歴の設定の更新に失敗しました".to_string())?;
+    *current = config;
+    drop(current);
+
+    if let Ok(mut requests) = RECENT_REQUESTS.lock() {
+        if !config.enabled {
+            requests.clear();
+        }
+        let capacity = config.capacity.min(MAX_TRACE_CAPACITY);
+        while requests.len() > capacity {
+            requests.pop_front();
+        }
+    }
+    log::info!("Request trace config changed: {:?}", config);
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::openai_proxy::OpenAIResponse;
+
+const MAX_CACHE_ENTRIES: usize = 256;
+
+static RESPONSE_CACHE: Lazy<Mutex<HashMap<String, CacheEntry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct CacheEntry {
+    response: OpenAIResponse,
+    etag: Option<String>,
+    stored_at: Instant,
+    ttl: Duration,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self) -> bool {
+        self.stored_at.elapsed() < self.ttl
+    }
+
+    /// 期限切れでも ETag があれば条件付きリクエストで再利用できる
+    fn is_reusable(&self) -> bool {
+        self.is_fresh() || self.etag.is_some()
+    }
+}
+
+pub enum CacheLookup {
+    /// 有効期限内のキャッシュ
+    Fresh(Box<OpenAIResponse>),
+    /// 期限切れだが ETag があるため If-None-Match で再検証できる
+    Stale { etag: String },
+    Miss,
+}
+
+/// キャッシュキーを作成する（メソッド・URL・追加ヘッダー・API キーのハッシュ）
+///
+/// API キーが異なれば組織も異なり得るため、キーのハッシュもキーに含める。
+pub fn cache_key(
+    method: &str,
+    url: &str,
+    additional_headers: &Option<HashMap<String, String>>,
+    api_key: &str,
+) -> String {
+    let mut headers: Vec<(String, &String)> = additional_headers
+        .iter()
+        .flatten()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .collect();
+    headers.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(method.to_ascii_uppercase().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(url.as_bytes());
+    for (name, value) in headers {
+        hasher.update(b"\n");
+        hasher.update(name.as_bytes());
+        hasher.update(b":");
+        hasher.update(value.as_bytes());
+    }
+    hasher.update(b"\n");
+    hasher.update(api_key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// キャッシュを検索する（ETag のない期限切れエントリは削除する）
+pub fn lookup(key: &str) -> CacheLookup {
+    let mut cache = match RESPONSE_CACHE.lock() {
+        Ok(cache) => cache,
+        Err(_) => return CacheLookup::Miss,
+    };
+    let result = match cache.get(key) {
+        Some(entry) if entry.is_fresh() => CacheLookup::Fresh(Box::new(entry.response.clone())),
+        Some(CacheEntry { etag: Some(etag), .. }) => CacheLookup::Stale { etag: etag.clone() },
+        Some(_) | None => CacheLookup::Miss,
+    };
+    if matches!(result, CacheLookup::Miss) {
+        cache.remove(key);
+    }
+    result
+}
+
+/// 304 Not Modified を受けたエントリの有効期限を延長し、キャッシュ済みレスポンスを返す
+pub fn revalidate(key: &str) -> Option<OpenAIResponse> {
+    let mut cache = RESPONSE_CACHE.lock().ok()?;
+    let entry = cache.get_mut(key)?;
+    entry.stored_at = Instant::now();
+    Some(entry.response.clone())
+}
+
+pub fn store(key: String, response: &OpenAIResponse, etag: Option<String>, ttl: Duration) {
+    let mut cache = match RESPONSE_CACHE.lock() {
+        Ok(cache) => cache,
+        Err(_) => return,
+    };
+
+    cache.retain(|_, entry| entry.is_reusable());
+    if cache.len() >= MAX_CACHE_ENTRIES && !cache.contains_key(&key) {
+        // 上限に達したら最も古いエントリを削除する
+        let oldest = cache
+            .iter()
+            .min_by_key(|(_, entry)| entry.stored_at)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+
+    cache.insert(
+        key,
+        CacheEntry {
+            response: response.clone(),
+            etag,
+            stored_at: Instant::now(),
+            ttl,
+        },
+    );
+}
+
+#[tauri::command]
+pub fn clear_response_cache() -> Result<usize, String> {
+    let mut cache = RESPONSE_CACHE
+        .lock()
+        .map_err(|_| "レスポンスキャッシュのクリアに失敗しました".to_string())?;
+    let cleared = cache.len();
+    cache.clear();
+    log::info!("Response cache cleared ({} entries)", cleared);
+    Ok(cleared)
+}
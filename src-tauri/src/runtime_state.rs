@@ -0,0 +1,45 @@
+use serde::Serialize;
+
+use crate::{circuit_breaker, metrics, openai_proxy, response_cache};
+
+/// reset_runtime_state でリセットした件数
+#[derive(Debug, Serialize, Clone)]
+pub struct RuntimeStateReset {
+    pub cached_responses: usize,
+    pub circuits: usize,
+    pub clients: usize,
+    pub metrics_reset: bool,
+}
+
+/// 設定変更前の接続先・プロキシに結びついた実行時の状態を破棄する
+///
+/// - レスポンスキャッシュ: すべて削除する（変更前の設定で取得した応答を返さない）
+/// - サーキットブレーカー: すべての遮断状態と失敗回数を消す
+/// - HTTP クライアント: 使い回しているクライアントと接続プールを破棄し、次のリクエストで作り直す
+/// - メトリクス: `reset_metrics` が true の場合のみリセットする（通常は累計を残す）
+///
+/// 実行中のリクエスト、アップロードセッション、リクエスト履歴、および set_* で変更した設定
+/// （ログ設定・既定のプロキシ・接続プール・プロバイダープロファイルなど）はそのまま残す。
+pub fn reset(reset_metrics: bool) -> Result<RuntimeStateReset, String> {
+    let cached_responses = response_cache::clear_response_cache()?;
+    let circuits = circuit_breaker::reset_circuit_breaker(None)?;
+    let clients = openai_proxy::clear_client_cache();
+    if reset_metrics {
+        metrics::reset_proxy_metrics()?;
+    }
+
+    let reset = RuntimeStateReset {
+        cached_responses,
+        circuits,
+        clients,
+        metrics_reset: reset_metrics,
+    };
+    log::info!("Runtime state reset: {:?}", reset);
+    Ok(reset)
+}
+
+/// config.pkg の再読み込み・既定のプロキシの変更時にも呼ばれる（メトリクスは残す）
+#[tauri::command]
+pub fn reset_runtime_state(reset_metrics: Option<bool>) -> Result<RuntimeStateReset, String> {
+    reset(reset_metrics.unwrap_or(false))
+}
@@ -1,7 +1,15 @@
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
-use tauri::Manager;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{Emitter, Manager};
+
+use crate::audit_log;
+use crate::runtime_state;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -29,9 +37,33 @@ pub struct SecureConfig {
     #[serde(default)]
     pub features: Option<SecureFeatureRestrictions>,
     #[serde(default)]
+    pub response_redaction: Option<ResponseRedactionConfig>,
+    #[serde(default)]
     pub signature: Option<String>,
 }
 
+/// API レスポンスを返す前に取り除く（またはマスクする）項目
+///
+/// `pointers` は JSON Pointer（例: `/choices/*/message/refusal`）。`*` は配列・オブジェクトのすべての要素に一致する。
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseRedactionConfig {
+    #[serde(default)]
+    pub pointers: Vec<String>,
+    #[serde(default)]
+    pub mode: RedactionMode,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RedactionMode {
+    /// 項目ごと削除する
+    #[default]
+    Remove,
+    /// 値を "[REDACTED]" に置き換える
+    Mask,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SecureConfigSearchPath {
@@ -48,7 +80,7 @@ pub struct SecureConfigResult {
     pub searched_paths: Vec<SecureConfigSearchPath>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SecureFeatureRestrictions {
     #[serde(default)]
@@ -61,29 +93,213 @@ pub struct SecureFeatureRestrictions {
     pub allow_chat_file_attachment: Option<bool>,
 }
 
-fn candidate_paths(app: &tauri::AppHandle) -> Vec<(PathBuf, String)> {
+impl SecureFeatureRestrictions {
+    /// 未指定の項目を「許可」として埋めた、実際に適用する制限を返す
+    fn effective(&self) -> Self {
+        Self {
+            allow_web_search: Some(self.allow_web_search.unwrap_or(true)),
+            allow_vector_store: Some(self.allow_vector_store.unwrap_or(true)),
+            allow_file_upload: Some(self.allow_file_upload.unwrap_or(true)),
+            allow_chat_file_attachment: Some(self.allow_chat_file_attachment.unwrap_or(true)),
+        }
+    }
+}
+
+const CONFIG_PATH_ENV: &str = "AI_SDK_CONFIG_PATH";
+const FEATURE_RESTRICTIONS_CHANGED_EVENT: &str = "feature-restrictions-changed";
+
+/// 現在適用中の機能制限（UI の表示制御とプロキシ側の制御で共有する）
+static ACTIVE_FEATURE_RESTRICTIONS: Lazy<RwLock<SecureFeatureRestrictions>> =
+    Lazy::new(|| RwLock::new(SecureFeatureRestrictions::default().effective()));
+
+/// 現在適用中のレスポンスのマスク設定（プロキシ側でレスポンスを返す前に適用する）
+static ACTIVE_RESPONSE_REDACTION: Lazy<RwLock<ResponseRedactionConfig>> =
+    Lazy::new(|| RwLock::new(ResponseRedactionConfig::default()));
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigSource {
+    /// 候補パスから自動検出した
+    AutoDetected,
+    /// load_secure_config_from_path でパスを指定して読み込んだ
+    ExplicitPath,
+}
+
+/// 現在適用中の config.pkg の情報（診断表示用）
+#[derive(Debug, Clone)]
+struct ActiveConfigInfo {
+    path: String,
+    source: ConfigSource,
+    signature_status: SignatureStatus,
+    version: Option<u32>,
+    whitelist_count: usize,
+    has_admin_password: bool,
+}
+
+static ACTIVE_CONFIG: Lazy<RwLock<Option<ActiveConfigInfo>>> = Lazy::new(|| RwLock::new(None));
+
+fn set_active_config(info: Option<ActiveConfigInfo>) {
+    match ACTIVE_CONFIG.write() {
+        Ok(mut active) => *active = info,
+        Err(_) => log::error!("Failed to record active secure config: lock poisoned"),
+    }
+}
+
+/// 読み込んだ設定の機能制限を適用し、変化があればフロントエンドへ通知する
+fn apply_feature_restrictions(app: &tauri::AppHandle, config: Option<&SecureConfig>) {
+    let restrictions = config
+        .and_then(|config| config.features.clone())
+        .unwrap_or_default()
+        .effective();
+
+    let mut active = match ACTIVE_FEATURE_RESTRICTIONS.write() {
+        Ok(active) => active,
+        Err(_) => {
+            log::error!("Failed to update feature restrictions: lock poisoned");
+            return;
+        }
+    };
+    if *active == restrictions {
+        return;
+    }
+    *active = restrictions.clone();
+    drop(active);
+
+    log::info!("Feature restrictions changed: {:?}", restrictions);
+    if let Err(err) = app.emit(FEATURE_RESTRICTIONS_CHANGED_EVENT, restrictions) {
+        log::warn!("Failed to emit {}: {}", FEATURE_RESTRICTIONS_CHANGED_EVENT, err);
+    }
+}
+
+/// プロキシ側で機能制限を確認するための、現在適用中の制限
+pub fn active_feature_restrictions() -> SecureFeatureRestrictions {
+    ACTIVE_FEATURE_RESTRICTIONS
+        .read()
+        .map(|active| active.clone())
+        .unwrap_or_else(|_| SecureFeatureRestrictions::default().effective())
+}
+
+/// 読み込んだ設定のレスポンスのマスク設定を適用する（JSON Pointer として不正なものは無視する）
+fn apply_response_redaction(config: Option<&SecureConfig>) {
+    let mut redaction = config
+        .and_then(|config| config.response_redaction.clone())
+        .unwrap_or_default();
+    redaction.pointers.retain(|pointer| {
+        let valid = pointer.starts_with('/');
+        if !valid {
+            log::warn!("Ignoring invalid response redaction pointer: {}", pointer);
+        }
+        valid
+    });
+
+    match ACTIVE_RESPONSE_REDACTION.write() {
+        Ok(mut active) => {
+            if *active != redaction {
+                log::info!(
+                    "Response redaction changed: {} pointer(s), mode {:?}",
+                    redaction.pointers.len(),
+                    redaction.mode
+                );
+            }
+            *active = redaction;
+        }
+        Err(_) => log::error!("Failed to update response redaction: lock poisoned"),
+    }
+}
+
+/// 設定を読み込み直したら、変更前の設定に結びついたキャッシュ・遮断状態を破棄する
+fn reset_runtime_state_after_reload() {
+    if let Err(err) = runtime_state::reset(false) {
+        log::error!("Failed to reset runtime state after config reload: {}", err);
+    }
+}
+
+pub fn active_response_redaction() -> ResponseRedactionConfig {
+    ACTIVE_RESPONSE_REDACTION
+        .read()
+        .map(|active| active.clone())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_feature_restrictions() -> Result<SecureFeatureRestrictions, String> {
+    ACTIVE_FEATURE_RESTRICTIONS
+        .read()
+        .map(|active| active.clone())
+        .map_err(|_| "機能制限の取得に失敗しました".to_string())
+}
+
+/// ファイルパスならそのまま、フォルダなら config.pkg を付けて返す
+fn resolve_config_file(raw: &Path) -> PathBuf {
+    let is_pkg_file = raw
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("pkg"))
+        .unwrap_or(false);
+    if is_pkg_file || raw.is_file() {
+        raw.to_path_buf()
+    } else {
+        raw.join("config.pkg")
+    }
+}
+
+fn push_candidate(paths: &mut Vec<(PathBuf, String)>, candidate: PathBuf, label: String) {
+    if !paths.iter().any(|(existing, _)| existing == &candidate) {
+        paths.push((candidate, label));
+    }
+}
+
+/// 追加の検索パス（明示指定 → 環境変数の順）を既定の候補より優先して並べる
+fn candidate_paths(app: &tauri::AppHandle, extra_paths: &[String]) -> Vec<(PathBuf, String)> {
     let resolver = app.path();
     let mut paths: Vec<(PathBuf, String)> = Vec::new();
 
+    for extra in extra_paths {
+        let trimmed = extra.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        push_candidate(
+            &mut paths,
+            resolve_config_file(Path::new(trimmed)),
+            "管理者が指定したパス".to_string(),
+        );
+    }
+
+    if let Some(env_value) = std::env::var_os(CONFIG_PATH_ENV) {
+        for env_path in std::env::split_paths(&env_value) {
+            if env_path.as_os_str().is_empty() {
+                continue;
+            }
+            push_candidate(
+                &mut paths,
+                resolve_config_file(&env_path),
+                format!("環境変数 {} で指定されたパス", CONFIG_PATH_ENV),
+            );
+        }
+    }
+
     if let Ok(config_dir) = resolver.app_config_dir() {
-        paths.push((
+        push_candidate(
+            &mut paths,
             config_dir.join("config.pkg"),
             "アプリの設定フォルダ（自動コピー先）".to_string(),
-        ));
+        );
     }
 
     if let Ok(exe_dir) = resolver.executable_dir() {
-        let candidate = exe_dir.join("config.pkg");
-        if !paths.iter().any(|(existing, _)| existing == &candidate) {
-            paths.push((candidate, "アプリを起動したフォルダ".to_string()));
-        }
+        push_candidate(
+            &mut paths,
+            exe_dir.join("config.pkg"),
+            "アプリを起動したフォルダ".to_string(),
+        );
     }
 
     if let Ok(resource_dir) = resolver.resource_dir() {
-        let candidate = resource_dir.join("config.pkg");
-        if !paths.iter().any(|(existing, _)| existing == &candidate) {
-            paths.push((candidate, "アプリのリソースフォルダ".to_string()));
-        }
+        push_candidate(
+            &mut paths,
+            resource_dir.join("config.pkg"),
+            "アプリのリソースフォルダ".to_string(),
+        );
     }
 
     paths
@@ -104,8 +320,11 @@ pub struct ConfigCandidatesResult {
 }
 
 #[tauri::command]
-pub fn get_config_candidates(app: tauri::AppHandle) -> Result<ConfigCandidatesResult, String> {
-    let candidates = candidate_paths(&app);
+pub fn get_config_candidates(
+    app: tauri::AppHandle,
+    extra_paths: Option<Vec<String>>,
+) -> Result<ConfigCandidatesResult, String> {
+    let candidates = candidate_paths(&app, extra_paths.as_deref().unwrap_or_default());
 
     let result_candidates = candidates
         .iter()
@@ -121,31 +340,215 @@ pub fn get_config_candidates(app: tauri::AppHandle) -> Result<ConfigCandidatesRe
     })
 }
 
-#[tauri::command]
-pub fn load_secure_config_from_path(path: String) -> Result<SecureConfigResult, String> {
-    let path_buf = PathBuf::from(&path);
+// config.pkg の署名検証に使う Ed25519 公開鍵（hex）
+// 実行時の環境変数では差し替えられないよう、ビルド時に埋め込む
+const CONFIG_PUBLIC_KEY_HEX: Option<&str> = option_env!("AI_SDK_CONFIG_PUBLIC_KEY");
 
-    if !path_buf.exists() {
-        return Err(format!("指定されたパスにファイルが存在しません: {}", path));
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    /// 埋め込み公開鍵で署名を検証できた
+    Verified,
+    /// 署名はあるが、公開鍵が埋め込まれていないため検証できない
+    Unverifiable,
+    Unsigned,
+    /// 署名が一致しない（改ざんの可能性）
+    Invalid,
+}
+
+impl SignatureStatus {
+    /// 候補を選ぶときの優先度
+    ///
+    /// 検証できない署名は誰でも付けられるため、署名なしと同じ扱いにする
+    /// （"signature" を付けただけの設定で本来の設定を上書きされないようにする）。
+    fn trust_rank(self) -> u8 {
+        match self {
+            SignatureStatus::Verified => 2,
+            SignatureStatus::Unverifiable | SignatureStatus::Unsigned => 1,
+            SignatureStatus::Invalid => 0,
+        }
     }
+}
 
-    log::info!("Loading secure config from {:?}", path_buf);
+struct LoadedConfig {
+    config: SecureConfig,
+    signature_status: SignatureStatus,
+}
+
+impl LoadedConfig {
+    fn active_info(&self, path: String, source: ConfigSource) -> ActiveConfigInfo {
+        ActiveConfigInfo {
+            path,
+            source,
+            signature_status: self.signature_status,
+            version: self.config.version,
+            whitelist_count: self.config.org_whitelist.len(),
+            has_admin_password: self
+                .config
+                .admin_password_hash
+                .as_deref()
+                .is_some_and(|hash| !hash.trim().is_empty()),
+        }
+    }
+}
+
+fn audit_config_load(app: &tauri::AppHandle, path: &str, result: &Result<LoadedConfig, String>) {
+    let (status, params) = match result {
+        Ok(loaded) => (
+            "success",
+            serde_json::json!({
+                "path": path,
+                "version": loaded.config.version,
+                "whitelistCount": loaded.config.org_whitelist.len(),
+                "signatureStatus": loaded.signature_status,
+            }),
+        ),
+        Err(err) => ("failure", serde_json::json!({ "path": path, "error": err })),
+    };
+    audit_log::record(app, "secure_config_load", status, params);
+}
+
+/// オブジェクトのキーを再帰的に並べ替えた値を返す（署名対象の正規化）
+fn canonical_json(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let sorted = keys
+                .into_iter()
+                .map(|key| (key.clone(), canonical_json(&map[key])))
+                .collect();
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical_json).collect()),
+        other => other.clone(),
+    }
+}
+
+fn trusted_public_key() -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(CONFIG_PUBLIC_KEY_HEX?.trim()).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// `signature` を除いた設定をキー順に並べたコンパクトな JSON に対して、
+/// Base64 エンコードされた Ed25519 署名を検証する
+fn verify_signature(raw: &Value) -> SignatureStatus {
+    verify_signature_with(raw, trusted_public_key())
+}
+
+fn verify_signature_with(raw: &Value, public_key: Option<VerifyingKey>) -> SignatureStatus {
+    let signature = match raw.get("signature").and_then(Value::as_str).map(str::trim) {
+        Some(signature) if !signature.is_empty() => signature,
+        _ => return SignatureStatus::Unsigned,
+    };
+    let public_key = match public_key {
+        Some(public_key) => public_key,
+        None => return SignatureStatus::Unverifiable,
+    };
+    let signature = match general_purpose::STANDARD
+        .decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+    {
+        Some(signature) => signature,
+        None => return SignatureStatus::Invalid,
+    };
 
-    let data = fs::read(&path_buf).map_err(|err| {
+    let mut payload = canonical_json(raw);
+    if let Value::Object(map) = &mut payload {
+        map.remove("signature");
+    }
+    let payload = match serde_json::to_vec(&payload) {
+        Ok(payload) => payload,
+        Err(_) => return SignatureStatus::Invalid,
+    };
+
+    if public_key.verify(&payload, &signature).is_ok() {
+        SignatureStatus::Verified
+    } else {
+        SignatureStatus::Invalid
+    }
+}
+
+fn read_secure_config(path: &Path) -> Result<LoadedConfig, String> {
+    let data = fs::read(path).map_err(|err| {
         format!(
             "config.pkg の読み込みに失敗しました ({}): {}",
-            path,
+            path.display(),
             err
         )
     })?;
+    parse_secure_config(path, &data)
+}
 
-    let config: SecureConfig = serde_json::from_slice(&data).map_err(|err| {
+fn parse_secure_config(path: &Path, data: &[u8]) -> Result<LoadedConfig, String> {
+    let parse_error = |err: serde_json::Error| {
         format!(
             "config.pkg の解析に失敗しました ({}): {}",
-            path,
+            path.display(),
             err
         )
-    })?;
+    };
+    let raw: Value = serde_json::from_slice(data).map_err(parse_error)?;
+    let signature_status = verify_signature(&raw);
+    let config = serde_json::from_value(raw).map_err(parse_error)?;
+
+    Ok(LoadedConfig {
+        config,
+        signature_status,
+    })
+}
+
+/// 署名が一致しない設定は改ざんの可能性があるため適用しない
+fn reject_invalid_signature(path: &Path, loaded: LoadedConfig) -> Result<LoadedConfig, String> {
+    if loaded.signature_status == SignatureStatus::Invalid {
+        return Err(format!(
+            "config.pkg の署名を検証できませんでした。改ざんされている可能性があります ({})",
+            path.display()
+        ));
+    }
+    Ok(loaded)
+}
+
+/// 公開鍵が埋め込まれている場合は、検証済みの署名がある設定だけを受け付ける
+fn require_verified_signature(path: &Path, loaded: LoadedConfig, key_embedded: bool) -> Result<LoadedConfig, String> {
+    if key_embedded && loaded.signature_status != SignatureStatus::Verified {
+        return Err(format!(
+            "config.pkg に有効な署名がありません。署名済みの設定ファイルを指定してください ({})",
+            path.display()
+        ));
+    }
+    Ok(loaded)
+}
+
+fn searched_paths(candidates: &[(PathBuf, String)]) -> Vec<SecureConfigSearchPath> {
+    candidates
+        .iter()
+        .map(|(candidate_path, label)| SecureConfigSearchPath {
+            path: candidate_path.display().to_string(),
+            label: label.clone(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn load_secure_config_from_path(app: tauri::AppHandle, path: String) -> Result<SecureConfigResult, String> {
+    let path_buf = PathBuf::from(&path);
+
+    if !path_buf.exists() {
+        return Err(format!("指定されたパスにファイルが存在しません: {}", path));
+    }
+
+    log::info!("Loading secure config from {:?}", path_buf);
+
+    let loaded = read_secure_config(&path_buf).and_then(|loaded| reject_invalid_signature(&path_buf, loaded));
+    audit_config_load(&app, &path, &loaded);
+    let loaded = loaded?;
+    set_active_config(Some(loaded.active_info(path.clone(), ConfigSource::ExplicitPath)));
+    let config = loaded.config;
+    apply_feature_restrictions(&app, Some(&config));
+    apply_response_redaction(Some(&config));
+    reset_runtime_state_after_reload();
 
     Ok(SecureConfigResult {
         config: Some(config),
@@ -154,59 +557,596 @@ pub fn load_secure_config_from_path(path: String) -> Result<SecureConfigResult,
     })
 }
 
-#[tauri::command]
-pub fn load_secure_config(app: tauri::AppHandle) -> Result<SecureConfigResult, String> {
-    let candidates = candidate_paths(&app);
+/// 存在する候補をすべて読み込み、最も信頼度の高いもの（同順位なら候補順で先のもの）を選ぶ
+///
+/// 読み込みに失敗した候補は、他に読み込めた候補がなければそのエラーを返す。
+fn select_trusted_config(candidates: &[(PathBuf, String)]) -> Option<(PathBuf, Result<LoadedConfig, String>)> {
+    let mut best: Option<(PathBuf, LoadedConfig)> = None;
+    let mut first_error: Option<(PathBuf, String)> = None;
 
-    for (path, _) in candidates.iter() {
-        if !path.exists() {
-            continue;
+    for (path, _) in candidates.iter().filter(|(path, _)| path.exists()) {
+        match read_secure_config(path) {
+            Ok(loaded) => {
+                let is_better = match &best {
+                    Some((_, current)) => {
+                        loaded.signature_status.trust_rank() > current.signature_status.trust_rank()
+                    }
+                    None => true,
+                };
+                if is_better {
+                    best = Some((path.clone(), loaded));
+                }
+            }
+            Err(err) => {
+                log::warn!("{}", err);
+                if first_error.is_none() {
+                    first_error = Some((path.clone(), err));
+                }
+            }
         }
+    }
 
-        log::info!("Loading secure config from {:?}", path);
+    match (best, first_error) {
+        (Some((path, loaded)), _) => {
+            let loaded = reject_invalid_signature(&path, loaded);
+            Some((path, loaded))
+        }
+        (None, Some((path, err))) => Some((path, Err(err))),
+        (None, None) => None,
+    }
+}
 
-        let data = fs::read(path).map_err(|err| {
-            format!(
-                "config.pkg の読み込みに失敗しました ({}): {}",
-                path.display(),
-                err
-            )
-        })?;
+#[tauri::command]
+pub fn load_secure_config(
+    app: tauri::AppHandle,
+    extra_paths: Option<Vec<String>>,
+) -> Result<SecureConfigResult, String> {
+    let candidates = candidate_paths(&app, extra_paths.as_deref().unwrap_or_default());
 
-        let config: SecureConfig = serde_json::from_slice(&data).map_err(|err| {
-            format!(
-                "config.pkg の解析に失敗しました ({}): {}",
-                path.display(),
-                err
-            )
-        })?;
+    if let Some((path, loaded)) = select_trusted_config(&candidates) {
+        log::info!("Loading secure config from {:?}", path);
 
-        let searched_paths = candidates
-            .iter()
-            .map(|(candidate_path, label)| SecureConfigSearchPath {
-                path: candidate_path.display().to_string(),
-                label: label.clone(),
-            })
-            .collect();
+        audit_config_load(&app, &path.display().to_string(), &loaded);
+        let loaded = loaded?;
+        log::info!("Secure config signature status: {:?}", loaded.signature_status);
+        set_active_config(Some(loaded.active_info(path.display().to_string(), ConfigSource::AutoDetected)));
+        apply_feature_restrictions(&app, Some(&loaded.config));
+        apply_response_redaction(Some(&loaded.config));
+        reset_runtime_state_after_reload();
 
         return Ok(SecureConfigResult {
-            config: Some(config),
+            config: Some(loaded.config),
             path: Some(path.display().to_string()),
-            searched_paths,
+            searched_paths: searched_paths(&candidates),
         });
     }
 
-    let searched_paths = candidates
-        .iter()
-        .map(|(candidate_path, label)| SecureConfigSearchPath {
-            path: candidate_path.display().to_string(),
-            label: label.clone(),
-        })
-        .collect();
+    set_active_config(None);
+    apply_feature_restrictions(&app, None);
+    apply_response_redaction(None);
+    reset_runtime_state_after_reload();
 
     Ok(SecureConfigResult {
         config: None,
         path: None,
-        searched_paths,
+        searched_paths: searched_paths(&candidates),
+    })
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPathReport {
+    pub path: String,
+    pub label: String,
+    pub signature_status: Option<SignatureStatus>,
+    pub version: Option<u32>,
+    pub whitelist_count: Option<usize>,
+    pub features: Option<SecureFeatureRestrictions>,
+    /// 読み込み時に採用される候補かどうか
+    pub selected: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigConsistencyReport {
+    pub consistent: bool,
+    pub entries: Vec<ConfigPathReport>,
+    pub mismatches: Vec<String>,
+}
+
+fn whitelist_org_ids(config: &SecureConfig) -> Vec<&str> {
+    let mut org_ids: Vec<&str> = config
+        .org_whitelist
+        .iter()
+        .map(|entry| entry.org_id.as_str())
+        .collect();
+    org_ids.sort_unstable();
+    org_ids.dedup();
+    org_ids
+}
+
+fn effective_features(config: &SecureConfig) -> SecureFeatureRestrictions {
+    config.features.clone().unwrap_or_default().effective()
+}
+
+/// 検証済み以外の署名状態を報告用の説明にする
+///
+/// 公開鍵がなく検証できない署名は、未署名のコピーに偽の署名を付けて隠せるため未署名と同様に報告する。
+fn signature_problem(status: SignatureStatus) -> Option<&'static str> {
+    match status {
+        SignatureStatus::Verified => None,
+        SignatureStatus::Unverifiable => Some("署名を検証できません（公開鍵が埋め込まれていません）"),
+        SignatureStatus::Unsigned => Some("署名がありません"),
+        SignatureStatus::Invalid => Some("署名が一致しません（改ざんの可能性があります）"),
+    }
+}
+
+/// 全候補パスの config.pkg を比較し、署名の有無やホワイトリスト・機能制限の食い違いを報告する
+///
+/// 権限の緩いコピーを優先度の高い場所に置いて本来の設定を隠す「差し替え」を検出するためのもの。
+#[tauri::command]
+pub fn audit_config_consistency(
+    app: tauri::AppHandle,
+    extra_paths: Option<Vec<String>>,
+) -> Result<ConfigConsistencyReport, String> {
+    let candidates = candidate_paths(&app, extra_paths.as_deref().unwrap_or_default());
+    let selected_path = select_trusted_config(&candidates).map(|(path, _)| path);
+
+    let loaded: Vec<(&PathBuf, &String, Result<LoadedConfig, String>)> = candidates
+        .iter()
+        .filter(|(path, _)| path.exists())
+        .map(|(path, label)| (path, label, read_secure_config(path)))
+        .collect();
+
+    let reference = loaded.iter().find_map(|(path, _, result)| match result {
+        Ok(config) if Some(*path) == selected_path.as_ref() => Some(&config.config),
+        _ => None,
+    });
+
+    let mut mismatches = Vec::new();
+    let mut entries = Vec::new();
+    for (path, label, result) in &loaded {
+        let display_path = path.display().to_string();
+        let selected = Some(*path) == selected_path.as_ref();
+        match result {
+            Ok(current) => {
+                if let Some(problem) = signature_problem(current.signature_status) {
+                    mismatches.push(format!("{}: {}", display_path, problem));
+                }
+
+                if let Some(reference) = reference.filter(|_| !selected) {
+                    if whitelist_org_ids(&current.config) != whitelist_org_ids(reference) {
+                        mismatches.push(format!("{}: ホワイトリストが採用中の設定と異なります", display_path));
+                    }
+                    if effective_features(&current.config) != effective_features(reference) {
+                        mismatches.push(format!("{}: 機能制限が採用中の設定と異なります", display_path));
+                    }
+                    if current.config.admin_password_hash != reference.admin_password_hash {
+                        mismatches.push(format!("{}: 管理者パスワードが採用中の設定と異なります", display_path));
+                    }
+                }
+
+                entries.push(ConfigPathReport {
+                    path: display_path,
+                    label: (*label).clone(),
+                    signature_status: Some(current.signature_status),
+                    version: current.config.version,
+                    whitelist_count: Some(current.config.org_whitelist.len()),
+                    features: Some(effective_features(&current.config)),
+                    selected,
+                    error: None,
+                });
+            }
+            Err(err) => {
+                mismatches.push(format!("{}: 読み込みに失敗しました", display_path));
+                entries.push(ConfigPathReport {
+                    path: display_path,
+                    label: (*label).clone(),
+                    signature_status: None,
+                    version: None,
+                    whitelist_count: None,
+                    features: None,
+                    selected,
+                    error: Some(err.clone()),
+                });
+            }
+        }
+    }
+
+    let consistent = mismatches.is_empty();
+    if !consistent {
+        log::warn!("Secure config consistency check found {} issue(s)", mismatches.len());
+    }
+    audit_log::record(
+        &app,
+        "secure_config_consistency",
+        if consistent { "consistent" } else { "inconsistent" },
+        serde_json::json!({
+            "candidateCount": entries.len(),
+            "mismatchCount": mismatches.len(),
+        }),
+    );
+
+    Ok(ConfigConsistencyReport {
+        consistent,
+        entries,
+        mismatches,
+    })
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSyncResult {
+    pub source_path: String,
+    pub destination_path: String,
+    pub bytes_copied: u64,
+    pub signature_status: SignatureStatus,
+    pub replaced_existing: bool,
+}
+
+fn copy_config_to_app_dir(app: &tauri::AppHandle, source_path: &str) -> Result<ConfigSyncResult, String> {
+    let source = PathBuf::from(source_path.trim());
+    let data = fs::read(&source).map_err(|err| {
+        format!("config.pkg の読み込みに失敗しました ({}): {}", source.display(), err)
+    })?;
+    // 検証した内容そのものを書き込む（署名を保つため再シリアライズしない）
+    let loaded = parse_secure_config(&source, &data)
+        .and_then(|loaded| reject_invalid_signature(&source, loaded))
+        .and_then(|loaded| require_verified_signature(&source, loaded, trusted_public_key().is_some()))?;
+
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|err| format!("設定フォルダの取得に失敗しました: {}", err))?;
+    let destination = config_dir.join("config.pkg");
+
+    let same_file = match (fs::canonicalize(&source), fs::canonicalize(&destination)) {
+        (Ok(source), Ok(destination)) => source == destination,
+        _ => false,
+    };
+    if same_file {
+        return Err(format!("コピー元とコピー先が同じファイルです: {}", destination.display()));
+    }
+
+    fs::create_dir_all(&config_dir).map_err(|err| {
+        format!(
+            "設定フォルダを作成できませんでした。書き込み権限を確認してください ({}): {}",
+            config_dir.display(),
+            err
+        )
+    })?;
+
+    // 書き込み途中で失敗しても既存の config.pkg が壊れないよう、一時ファイルから置き換える
+    let replaced_existing = destination.exists();
+    let temp_path = config_dir.join("config.pkg.tmp");
+    fs::write(&temp_path, &data)
+        .and_then(|_| fs::rename(&temp_path, &destination))
+        .map_err(|err| {
+            let _ = fs::remove_file(&temp_path);
+            format!(
+                "設定フォルダに config.pkg を書き込めませんでした。書き込み権限を確認してください ({}): {}",
+                destination.display(),
+                err
+            )
+        })?;
+
+    Ok(ConfigSyncResult {
+        source_path: source.display().to_string(),
+        destination_path: destination.display().to_string(),
+        bytes_copied: data.len() as u64,
+        signature_status: loaded.signature_status,
+        replaced_existing,
     })
 }
+
+/// 検証済みの config.pkg をアプリの設定フォルダ（自動コピー先）にコピーする
+///
+/// exe と同じフォルダに置かれた設定をユーザープロファイルに残すために使う。
+/// 公開鍵が埋め込まれたビルドでは署名を検証できた設定のみコピーする。
+/// 公開鍵のないビルドでは署名を検証できないため、署名の一致しない設定以外は未署名でもコピーする。
+#[tauri::command]
+pub fn sync_config_to_app_dir(app: tauri::AppHandle, source_path: String) -> Result<ConfigSyncResult, String> {
+    log::info!("Copying secure config from {} to the app config dir", source_path);
+    let result = copy_config_to_app_dir(&app, &source_path);
+
+    match &result {
+        Ok(synced) => {
+            log::info!(
+                "Secure config copied to {} ({} bytes, signature: {:?})",
+                synced.destination_path, synced.bytes_copied, synced.signature_status
+            );
+            audit_log::record(
+                &app,
+                "secure_config_sync",
+                "success",
+                serde_json::json!({
+                    "path": synced.source_path,
+                    "destination": synced.destination_path,
+                    "signatureStatus": synced.signature_status,
+                    "replacedExisting": synced.replaced_existing,
+                }),
+            );
+        }
+        Err(err) => {
+            log::error!("Failed to copy secure config: {}", err);
+            audit_log::record(
+                &app,
+                "secure_config_sync",
+                "failure",
+                serde_json::json!({ "path": source_path, "error": err }),
+            );
+        }
+    }
+    result
+}
+
+// このアプリが解釈できる config.pkg の最新バージョン
+const SUPPORTED_CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValidationReport {
+    pub path: String,
+    pub valid: bool,
+    pub error: Option<String>,
+    pub error_line: Option<usize>,
+    pub error_column: Option<usize>,
+    pub signature_status: Option<SignatureStatus>,
+    pub version: Option<u32>,
+    pub whitelist_count: Option<usize>,
+    /// ファイルで明示的に指定されている機能制限（未指定の項目は None）
+    pub features: Option<SecureFeatureRestrictions>,
+    pub warnings: Vec<String>,
+}
+
+impl ConfigValidationReport {
+    fn failed(path: String, error: String, location: Option<(usize, usize)>) -> Self {
+        Self {
+            path,
+            valid: false,
+            error: Some(error),
+            error_line: location.map(|(line, _)| line),
+            error_column: location.map(|(_, column)| column),
+            signature_status: None,
+            version: None,
+            whitelist_count: None,
+            features: None,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// 配布前の config.pkg を検証する（適用中の設定や機能制限は変更しない）
+#[tauri::command]
+pub fn validate_secure_config(path: String) -> Result<ConfigValidationReport, String> {
+    if path.trim().is_empty() {
+        return Err("検証する config.pkg のパスを指定してください".to_string());
+    }
+    let path_buf = resolve_config_file(Path::new(path.trim()));
+    let display_path = path_buf.display().to_string();
+
+    let data = match fs::read(&path_buf) {
+        Ok(data) => data,
+        Err(err) => {
+            return Ok(ConfigValidationReport::failed(
+                display_path,
+                format!("config.pkg の読み込みに失敗しました: {}", err),
+                None,
+            ));
+        }
+    };
+
+    // 構文エラー・型の不一致はどちらも行と列を返す
+    let parsed = serde_json::from_slice::<Value>(&data)
+        .and_then(|raw| serde_json::from_slice::<SecureConfig>(&data).map(|config| (raw, config)));
+    let (raw, config) = match parsed {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            return Ok(ConfigValidationReport::failed(
+                display_path,
+                format!("config.pkg の解析に失敗しました: {}", err),
+                Some((err.line(), err.column())),
+            ));
+        }
+    };
+
+    let signature_status = verify_signature(&raw);
+    let mut warnings = Vec::new();
+    match config.version {
+        None => warnings.push("version が指定されていません".to_string()),
+        Some(version) if version > SUPPORTED_CONFIG_VERSION => warnings.push(format!(
+            "version {} はこのアプリが対応するバージョン ({}) より新しいため、一部の設定が無視される可能性があります",
+            version, SUPPORTED_CONFIG_VERSION
+        )),
+        Some(_) => {}
+    }
+    match signature_status {
+        SignatureStatus::Invalid => warnings.push("署名が一致しません".to_string()),
+        SignatureStatus::Unsigned => warnings.push("署名がありません".to_string()),
+        SignatureStatus::Unverifiable => {
+            warnings.push("公開鍵が埋め込まれていないため署名を検証できません".to_string())
+        }
+        SignatureStatus::Verified => {}
+    }
+    let mut seen_org_ids = std::collections::HashSet::new();
+    for entry in &config.org_whitelist {
+        let org_id = entry.org_id.trim();
+        if org_id.is_empty() {
+            warnings.push(format!("orgId が空のエントリがあります ({})", entry.org_name));
+        } else if !seen_org_ids.insert(org_id) {
+            warnings.push(format!("orgId が重複しています: {}", org_id));
+        }
+    }
+
+    log::info!("Validated secure config {} ({} warning(s))", display_path, warnings.len());
+
+    Ok(ConfigValidationReport {
+        path: display_path,
+        valid: signature_status != SignatureStatus::Invalid,
+        error: None,
+        error_line: None,
+        error_column: None,
+        signature_status: Some(signature_status),
+        version: config.version,
+        whitelist_count: Some(config.org_whitelist.len()),
+        features: config.features,
+        warnings,
+    })
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveConfigSummary {
+    pub loaded: bool,
+    pub path: Option<String>,
+    pub source: Option<ConfigSource>,
+    pub signature_status: Option<SignatureStatus>,
+    pub version: Option<u32>,
+    pub whitelist_count: usize,
+    /// 管理者パスワードが設定されているか（ハッシュ自体は返さない）
+    pub has_admin_password: bool,
+    /// 現在適用中の機能制限（未指定の項目は許可として埋めたもの）
+    pub features: SecureFeatureRestrictions,
+    pub candidates: Vec<ConfigCandidate>,
+}
+
+/// サポート向けに、適用中の config.pkg とその内容の要約を 1 回で返す
+#[tauri::command]
+pub fn get_effective_config_summary(app: tauri::AppHandle) -> Result<EffectiveConfigSummary, String> {
+    let active = ACTIVE_CONFIG
+        .read()
+        .map(|active| active.clone())
+        .map_err(|_| "設定情報の取得に失敗しました".to_string())?;
+    let features = get_feature_restrictions()?;
+    let candidates = get_config_candidates(app, None)?.candidates;
+
+    Ok(match active {
+        Some(active) => EffectiveConfigSummary {
+            loaded: true,
+            path: Some(active.path),
+            source: Some(active.source),
+            signature_status: Some(active.signature_status),
+            version: active.version,
+            whitelist_count: active.whitelist_count,
+            has_admin_password: active.has_admin_password,
+            features,
+            candidates,
+        },
+        None => EffectiveConfigSummary {
+            loaded: false,
+            path: None,
+            source: None,
+            signature_status: None,
+            version: None,
+            whitelist_count: 0,
+            has_admin_password: false,
+            features,
+            candidates,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn signed(mut config: Value, key: &SigningKey) -> Value {
+        let payload = serde_json::to_vec(&canonical_json(&config)).unwrap();
+        let signature = general_purpose::STANDARD.encode(key.sign(&payload).to_bytes());
+        config["signature"] = Value::String(signature);
+        config
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys_recursively() {
+        let value = serde_json::json!({ "b": 1, "a": { "d": [ { "z": 1, "y": 2 } ], "c": true } });
+        assert_eq!(
+            serde_json::to_string(&canonical_json(&value)).unwrap(),
+            r#"{"a":{"c":true,"d":[{"y":2,"z":1}]},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn verify_signature_detects_valid_tampered_and_garbage_signatures() {
+        let key = signing_key();
+        let public_key = Some(key.verifying_key());
+        let config = signed(serde_json::json!({ "version": 1, "orgWhitelist": [] }), &key);
+        assert_eq!(verify_signature_with(&config, public_key), SignatureStatus::Verified);
+
+        let mut tampered = config.clone();
+        tampered["version"] = Value::from(2);
+        assert_eq!(verify_signature_with(&tampered, public_key), SignatureStatus::Invalid);
+
+        let mut garbage = config.clone();
+        garbage["signature"] = Value::String("x".to_string());
+        assert_eq!(verify_signature_with(&garbage, public_key), SignatureStatus::Invalid);
+
+        let unsigned = serde_json::json!({ "version": 1 });
+        assert_eq!(verify_signature_with(&unsigned, public_key), SignatureStatus::Unsigned);
+
+        // 公開鍵が埋め込まれていなければ検証できない
+        assert_eq!(verify_signature_with(&garbage, None), SignatureStatus::Unverifiable);
+    }
+
+    #[test]
+    fn unverifiable_signature_does_not_outrank_unsigned_config() {
+        assert_eq!(
+            SignatureStatus::Unverifiable.trust_rank(),
+            SignatureStatus::Unsigned.trust_rank()
+        );
+        assert!(SignatureStatus::Verified.trust_rank() > SignatureStatus::Unsigned.trust_rank());
+        assert!(SignatureStatus::Invalid.trust_rank() < SignatureStatus::Unsigned.trust_rank());
+    }
+
+    #[test]
+    fn only_verified_signatures_are_reported_as_consistent() {
+        assert_eq!(signature_problem(SignatureStatus::Verified), None);
+        assert!(signature_problem(SignatureStatus::Unverifiable).is_some());
+        assert!(signature_problem(SignatureStatus::Unsigned).is_some());
+        assert!(signature_problem(SignatureStatus::Invalid).is_some());
+    }
+
+    #[test]
+    fn select_trusted_config_prefers_candidate_order_over_bogus_signature() {
+        let dir = std::env::temp_dir().join(format!("secure-config-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let real = dir.join("real.pkg");
+        let shadow = dir.join("shadow.pkg");
+        fs::write(&real, r#"{"version":1,"adminPasswordHash":"real"}"#).unwrap();
+        fs::write(&shadow, r#"{"version":1,"adminPasswordHash":"shadow","signature":"x"}"#).unwrap();
+
+        let candidates = vec![
+            (real.clone(), "real".to_string()),
+            (shadow.clone(), "shadow".to_string()),
+        ];
+        let (path, loaded) = select_trusted_config(&candidates).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(path, real);
+        assert_eq!(loaded.unwrap().config.admin_password_hash.as_deref(), Some("real"));
+    }
+
+    #[test]
+    fn require_verified_signature_rejects_unverified_configs_when_key_is_embedded() {
+        let path = Path::new("config.pkg");
+        let load = |data: &str| parse_secure_config(path, data.as_bytes()).unwrap();
+        let unsigned = r#"{"version":1}"#;
+        let bogus = r#"{"version":1,"signature":"x"}"#;
+
+        assert!(require_verified_signature(path, load(unsigned), true).is_err());
+        assert!(require_verified_signature(path, load(bogus), true).is_err());
+        assert!(require_verified_signature(path, load(unsigned), false).is_ok());
+
+        let verified = LoadedConfig {
+            signature_status: SignatureStatus::Verified,
+            ..load(unsigned)
+        };
+        assert!(require_verified_signature(path, verified, true).is_ok());
+    }
+}